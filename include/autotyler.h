@@ -0,0 +1,129 @@
+/* C API of the autotyler library. Link against the cdylib from cargo build --release --lib.
+ *
+ * Make a job, set options and tiles the same way as on the command line, give it RGBA pixels and run it:
+ *
+ *     AutotylerJob *job = autotyler_job_new();
+ *     autotyler_job_set_option(job, "mode", "3x3");
+ *     autotyler_job_set_option(job, "size", "16");
+ *     autotyler_job_set_input(job, pixels, width, height);
+ *     if (autotyler_job_run(job) != 0)
+ *         fprintf(stderr, "%s\n", autotyler_job_error(job));
+ *     uint32_t out_width, out_height;
+ *     const uint8_t *out = autotyler_job_output(job, &out_width, &out_height);
+ *     ...
+ *     autotyler_job_free(job);
+ *
+ * Functions returning int return 0 on success and -1 on failure, with the message in autotyler_job_error().
+ * Pixels are width*height*4 bytes of RGBA, rows top to bottom. Strings are UTF-8.
+ *
+ * The autotyler_alloc(), autotyler_assemble() family is the lower level ABI made for wasm32 (src/wasm.rs), which runs
+ * a single mode over one buffer and needs nothing but plain structs.
+ */
+
+#ifndef AUTOTYLER_H
+#define AUTOTYLER_H
+
+/* Generated by cbindgen from src/capi.rs and src/wasm.rs with cbindgen.toml, don't edit by hand. */
+
+#include <stddef.h>
+#include <stdint.h>
+
+// Bumped whenever a function of the C API changes in a way that breaks callers.
+#define AUTOTYLER_API_VERSION 1
+
+typedef struct AutotylerJob AutotylerJob;
+
+// Either the output pixels or an error message, never both. On wasm32 this is 5 u32s in a row.
+typedef struct AutotylerAssembleResult {
+  // width*height*4 bytes of RGBA
+  uint8_t *pixels;
+  uint32_t width;
+  uint32_t height;
+  // UTF-8, not null terminated
+  uint8_t *error;
+  uint32_t error_len;
+} AutotylerAssembleResult;
+
+// What the options on the command line would say. On wasm32 every field is 4 bytes, so this is 11 u32s in a row.
+typedef struct AutotylerAssembleOptions {
+  // 0 to 6 for basic, basic_border, 3x3, 3x3plus, 4x4, 4x4plus, minitiles
+  uint32_t mode;
+  uint32_t size;
+  // left, top, right, bottom, all 0 for the default of half the tile size
+  uint32_t edges[4];
+  uint32_t border;
+  // in tiles
+  uint32_t offset_x;
+  uint32_t offset_y;
+  // tile_count x,y pairs, or null for the default tile list
+  uint32_t tile_count;
+  const uint32_t *tiles;
+} AutotylerAssembleOptions;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// AUTOTYLER_API_VERSION of the library, to check against the header.
+uint32_t autotyler_api_version(void);
+
+// A job with the same defaults as the command line. It has to be freed with autotyler_job_free().
+struct AutotylerJob *autotyler_job_new(void);
+
+// Frees a job and everything it holds. Null is ignored.
+void autotyler_job_free(struct AutotylerJob *handle);
+
+// Sets a key=value option as given on the command line, like "mode" and "3x3". Options that name files are ignored by autotyler_job_run().
+int autotyler_job_set_option(struct AutotylerJob *handle,
+                             const char *key,
+                             const char *value);
+
+// Adds an entry to the tile list as given on the command line, like "0,2", "grass:1,0" or "0,0/0,3@0.5".
+int autotyler_job_add_tile(struct AutotylerJob *handle,
+                           const char *tile);
+
+// Gives the input image as width*height*4 bytes of RGBA, which are copied.
+int autotyler_job_set_input(struct AutotylerJob *handle,
+                            const uint8_t *rgba,
+                            uint32_t width,
+                            uint32_t height);
+
+// Adds an image for tiles of the tile list to name, in place of a file name.
+int autotyler_job_add_image(struct AutotylerJob *handle,
+                            const char *name,
+                            const uint8_t *rgba,
+                            uint32_t width,
+                            uint32_t height);
+
+// Runs the job on the input image and any added images. A panic is caught and returned as an error instead of unwinding into the caller.
+int autotyler_job_run(struct AutotylerJob *handle);
+
+// The output of the last successful run as width*height*4 bytes of RGBA, or null. It stays valid until the next run or until the job is freed.
+const uint8_t *autotyler_job_output(const struct AutotylerJob *handle,
+                                    uint32_t *width,
+                                    uint32_t *height);
+
+// The message of the last call that failed, or null if the last call worked. It stays valid until the next call on the job.
+const char *autotyler_job_error(const struct AutotylerJob *handle);
+
+// Allocates len bytes for the host to write into.
+uint8_t *autotyler_alloc(size_t len);
+
+// ptr must come from autotyler_alloc() with the same len.
+void autotyler_free(uint8_t *ptr, size_t len);
+
+// Runs one mode over width*height*4 bytes of RGBA. The result has to be freed with autotyler_free_result(). Null
+// pointers give an error in the result, like everything else that goes wrong.
+struct AutotylerAssembleResult *autotyler_assemble(const struct AutotylerAssembleOptions *options,
+                                                   const uint8_t *rgba,
+                                                   uint32_t width,
+                                                   uint32_t height);
+
+// result must come from autotyler_assemble() and not have been freed yet.
+void autotyler_free_result(struct AutotylerAssembleResult *result);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* AUTOTYLER_H */
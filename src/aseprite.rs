@@ -0,0 +1,469 @@
+// A reader for Aseprite's .ase/.aseprite files, following https://github.com/aseprite/aseprite/blob/main/docs/ase-file-specs.md
+// Only what's needed to get RGBA images out of them is read. Blend modes other than normal are drawn as normal,
+// and tilemap layers are skipped.
+
+use image::Rgba;
+use image::RgbaImage;
+
+// What to take out of an Aseprite file.
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct Options
+{
+    // only this layer (or group), even if it's hidden
+    pub layer : Option<String>,
+    // only the frames of this tag
+    pub tag : Option<String>,
+    // only these frames, counting from 0, both included
+    pub frame_range : Option<(u32, u32)>,
+    // every layer side by side instead of all of them drawn on top of each other
+    pub separate_layers : bool,
+}
+
+struct Layer
+{
+    name : String,
+    visible : bool,
+    // background layers are opaque, even where indexed pixels use the transparent index
+    background : bool,
+    // groups hold the layers right after them with a higher child level
+    group : bool,
+    tilemap : bool,
+    child_level : u16,
+    opacity : u8,
+}
+
+struct Cel
+{
+    layer : usize,
+    x : i32,
+    y : i32,
+    opacity : u8,
+    width : u32,
+    height : u32,
+    // RGBA, already converted from grayscale or indexed
+    pixels : Vec<u8>,
+}
+
+struct Frame
+{
+    // in milliseconds
+    duration : u32,
+    cels : Vec<Cel>,
+}
+
+pub struct Aseprite
+{
+    pub width : u32,
+    pub height : u32,
+    layers : Vec<Layer>,
+    frames : Vec<Frame>,
+    // name, first frame, last frame
+    tags : Vec<(String, u32, u32)>,
+}
+
+struct Reader<'a>
+{
+    data : &'a [u8],
+    pos : usize,
+}
+
+impl<'a> Reader<'a>
+{
+    fn bytes(&mut self, count : usize) -> Result<&'a [u8], String>
+    {
+        if self.pos + count > self.data.len()
+        {
+            return Err("the file ends early".to_string());
+        }
+        self.pos += count;
+        Ok(&self.data[self.pos - count..self.pos])
+    }
+    
+    fn u8(&mut self) -> Result<u8, String>
+    {
+        Ok(self.bytes(1)?[0])
+    }
+    
+    fn u16(&mut self) -> Result<u16, String>
+    {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+    
+    fn i16(&mut self) -> Result<i16, String>
+    {
+        Ok(self.u16()? as i16)
+    }
+    
+    fn u32(&mut self) -> Result<u32, String>
+    {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+    
+    fn string(&mut self) -> Result<String, String>
+    {
+        let len = self.u16()? as usize;
+        Ok(String::from_utf8_lossy(self.bytes(len)?).to_string())
+    }
+}
+
+// Turns the pixels of a cel into RGBA.
+fn to_rgba(data : &[u8], depth : u16, palette : &[[u8; 4]], transparent_index : Option<u8>, count : usize) -> Result<Vec<u8>, String>
+{
+    let bytes_per_pixel = (depth/8) as usize;
+    if data.len() < count*bytes_per_pixel
+    {
+        return Err("a cel has fewer pixels than its size says".to_string());
+    }
+    let mut pixels = Vec::with_capacity(count*4);
+    for i in 0..count
+    {
+        match depth
+        {
+            32 => pixels.extend_from_slice(&data[i*4..i*4 + 4]),
+            16 => pixels.extend_from_slice(&[data[i*2], data[i*2], data[i*2], data[i*2 + 1]]),
+            _ =>
+            {
+                let index = data[i];
+                if Some(index) == transparent_index
+                {
+                    pixels.extend_from_slice(&[0, 0, 0, 0]);
+                }
+                else
+                {
+                    pixels.extend_from_slice(palette.get(index as usize).unwrap_or(&[0, 0, 0, 0]));
+                }
+            }
+        }
+    }
+    Ok(pixels)
+}
+
+impl Aseprite
+{
+    pub fn read(data : &[u8]) -> Result<Aseprite, String>
+    {
+        let mut header = Reader { data, pos : 0 };
+        header.u32()?;
+        if header.u16()? != 0xA5E0
+        {
+            return Err("not an Aseprite file".to_string());
+        }
+        let frame_count = header.u16()?;
+        let width = header.u16()? as u32;
+        let height = header.u16()? as u32;
+        let depth = header.u16()?;
+        if depth != 32 && depth != 16 && depth != 8
+        {
+            return Err(format!("unsupported color depth {}", depth));
+        }
+        let flags = header.u32()?;
+        let layer_opacity_valid = flags & 1 != 0;
+        header.bytes(10)?;
+        let transparent_index = header.u8()?;
+        
+        let mut ase = Aseprite { width, height, layers : Vec::new(), frames : Vec::new(), tags : Vec::new() };
+        let mut palette = vec!([0u8; 4]; 256);
+        let mut new_palette = false;
+        let mut pos = 128;
+        for _ in 0..frame_count
+        {
+            let mut frame_reader = Reader { data, pos };
+            let frame_size = frame_reader.u32()? as usize;
+            if frame_reader.u16()? != 0xF1FA
+            {
+                return Err("broken frame header".to_string());
+            }
+            let old_chunk_count = frame_reader.u16()? as u32;
+            let duration = frame_reader.u16()? as u32;
+            frame_reader.bytes(2)?;
+            let chunk_count = match frame_reader.u32()?
+            {
+                0 => old_chunk_count,
+                count => count,
+            };
+            if frame_size < 16
+            {
+                return Err("broken frame header".to_string());
+            }
+            
+            // cels are only converted to RGBA at the end of the frame, as the palette can come after them
+            // (layer, x, y, opacity, width, height, raw pixels or the frame a linked cel points to)
+            let mut raw_cels = Vec::<(usize, i32, i32, u8, u32, u32, Result<Vec<u8>, usize>)>::new();
+            for _ in 0..chunk_count
+            {
+                let chunk_start = frame_reader.pos;
+                let chunk_size = frame_reader.u32()? as usize;
+                let chunk_type = frame_reader.u16()?;
+                if chunk_size < 6 || chunk_start + chunk_size > data.len()
+                {
+                    return Err("broken chunk header".to_string());
+                }
+                let mut chunk = Reader { data : &data[..chunk_start + chunk_size], pos : frame_reader.pos };
+                match chunk_type
+                {
+                    // layer
+                    0x2004 =>
+                    {
+                        let layer_flags = chunk.u16()?;
+                        let layer_type = chunk.u16()?;
+                        let child_level = chunk.u16()?;
+                        chunk.bytes(6)?;
+                        let opacity = chunk.u8()?;
+                        chunk.bytes(3)?;
+                        let name = chunk.string()?;
+                        ase.layers.push(Layer
+                        {
+                            name,
+                            visible : layer_flags & 1 != 0,
+                            background : layer_flags & 8 != 0,
+                            group : layer_type == 1,
+                            tilemap : layer_type == 2,
+                            child_level,
+                            opacity : if layer_opacity_valid { opacity } else { 255 },
+                        });
+                    }
+                    // cel
+                    0x2005 =>
+                    {
+                        let layer = chunk.u16()? as usize;
+                        let x = chunk.i16()? as i32;
+                        let y = chunk.i16()? as i32;
+                        let opacity = chunk.u8()?;
+                        let cel_type = chunk.u16()?;
+                        chunk.bytes(7)?;
+                        match cel_type
+                        {
+                            0 | 2 =>
+                            {
+                                let cel_width = chunk.u16()? as u32;
+                                let cel_height = chunk.u16()? as u32;
+                                let rest = &chunk.data[chunk.pos..];
+                                let raw = if cel_type == 0
+                                {
+                                    rest.to_vec()
+                                }
+                                else
+                                {
+                                    miniz_oxide::inflate::decompress_to_vec_zlib(rest).map_err(|err| format!("failed to decompress a cel: {:?}", err))?
+                                };
+                                raw_cels.push((layer, x, y, opacity, cel_width, cel_height, Ok(raw)));
+                            }
+                            1 => raw_cels.push((layer, x, y, opacity, 0, 0, Err(chunk.u16()? as usize))),
+                            // tilemaps
+                            _ => {}
+                        }
+                    }
+                    // palette
+                    0x2019 =>
+                    {
+                        new_palette = true;
+                        let size = chunk.u32()? as usize;
+                        let first = chunk.u32()? as usize;
+                        let last = chunk.u32()? as usize;
+                        chunk.bytes(8)?;
+                        if palette.len() < size
+                        {
+                            palette.resize(size, [0, 0, 0, 0]);
+                        }
+                        for index in first..=last
+                        {
+                            let entry_flags = chunk.u16()?;
+                            let colour = chunk.bytes(4)?;
+                            if index < palette.len()
+                            {
+                                palette[index] = [colour[0], colour[1], colour[2], colour[3]];
+                            }
+                            if entry_flags & 1 != 0
+                            {
+                                chunk.string()?;
+                            }
+                        }
+                    }
+                    // old palettes, only used if there's no new one
+                    0x0004 | 0x0011 if !new_palette =>
+                    {
+                        let packets = chunk.u16()?;
+                        let mut index = 0;
+                        for _ in 0..packets
+                        {
+                            index += chunk.u8()? as usize;
+                            let count = match chunk.u8()? { 0 => 256, count => count as usize };
+                            for _ in 0..count
+                            {
+                                let colour = chunk.bytes(3)?;
+                                let scale = |c : u8| if chunk_type == 0x0011 { (c << 2) | (c >> 4) } else { c };
+                                if index < palette.len()
+                                {
+                                    palette[index] = [scale(colour[0]), scale(colour[1]), scale(colour[2]), 255];
+                                }
+                                index += 1;
+                            }
+                        }
+                    }
+                    // tags
+                    0x2018 =>
+                    {
+                        let count = chunk.u16()?;
+                        chunk.bytes(8)?;
+                        for _ in 0..count
+                        {
+                            let from = chunk.u16()? as u32;
+                            let to = chunk.u16()? as u32;
+                            chunk.bytes(13)?;
+                            ase.tags.push((chunk.string()?, from, to));
+                        }
+                    }
+                    _ => {}
+                }
+                frame_reader.pos = chunk_start + chunk_size;
+            }
+            
+            let mut cels = Vec::new();
+            for (layer, x, y, opacity, cel_width, cel_height, raw) in raw_cels
+            {
+                match raw
+                {
+                    Ok(raw) =>
+                    {
+                        let background = ase.layers.get(layer).map(|layer| layer.background).unwrap_or(false);
+                        // no index is transparent on the background
+                        let transparent_index = if background { None } else { Some(transparent_index) };
+                        let pixels = to_rgba(&raw, depth, &palette, transparent_index, (cel_width*cel_height) as usize)?;
+                        cels.push(Cel { layer, x, y, opacity, width : cel_width, height : cel_height, pixels });
+                    }
+                    Err(linked_frame) =>
+                    {
+                        let linked = ase.frames.get(linked_frame).and_then(|frame| frame.cels.iter().find(|cel| cel.layer == layer));
+                        if let Some(linked) = linked
+                        {
+                            cels.push(Cel { layer, x, y, opacity, width : linked.width, height : linked.height, pixels : linked.pixels.clone() });
+                        }
+                    }
+                }
+            }
+            ase.frames.push(Frame { duration, cels });
+            pos += frame_size;
+        }
+        Ok(ase)
+    }
+    
+    // Whether each layer is hidden by itself or by a group it's in.
+    fn shown_layers(&self) -> Vec<bool>
+    {
+        let mut shown = Vec::new();
+        // visibility of the groups the current layer is in, by child level
+        let mut groups = Vec::<bool>::new();
+        for layer in &self.layers
+        {
+            groups.truncate(layer.child_level as usize);
+            let visible = layer.visible && groups.iter().all(|&group| group);
+            shown.push(visible);
+            if layer.group
+            {
+                groups.resize(layer.child_level as usize, true);
+                groups.push(layer.visible);
+            }
+        }
+        shown
+    }
+    
+    // The index of the named layer and of every layer in it if it's a group.
+    fn layer_and_children(&self, name : &str) -> Result<Vec<usize>, String>
+    {
+        let index = self.layers.iter().position(|layer| layer.name == name).ok_or_else(|| format!("there is no layer called {}", name))?;
+        let level = self.layers[index].child_level;
+        let children = self.layers[index + 1..].iter().take_while(|layer| layer.child_level > level).count();
+        Ok((index..=index + children).collect())
+    }
+    
+    // Draws the given layers of a frame on top of each other.
+    fn draw(&self, frame : usize, layers : &[usize]) -> RgbaImage
+    {
+        let mut img = RgbaImage::new(self.width, self.height);
+        let mut cels = self.frames[frame].cels.iter().filter(|cel| layers.contains(&cel.layer)).collect::<Vec<_>>();
+        cels.sort_by_key(|cel| cel.layer);
+        for cel in cels
+        {
+            let layer_opacity = self.layers.get(cel.layer).map(|layer| layer.opacity).unwrap_or(255) as u32;
+            for cy in 0..cel.height
+            {
+                for cx in 0..cel.width
+                {
+                    let (x, y) = (cel.x + cx as i32, cel.y + cy as i32);
+                    if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32
+                    {
+                        continue;
+                    }
+                    let i = ((cy*cel.width + cx)*4) as usize;
+                    let src = &cel.pixels[i..i + 4];
+                    let alpha = src[3] as u32*cel.opacity as u32*layer_opacity/(255*255);
+                    if alpha == 0
+                    {
+                        continue;
+                    }
+                    let dst = img.get_pixel(x as u32, y as u32);
+                    let out_alpha = alpha + dst[3] as u32*(255 - alpha)/255;
+                    let mix = |s : u8, d : u8| ((s as u32*alpha + d as u32*dst[3] as u32*(255 - alpha)/255)/out_alpha) as u8;
+                    let px = Rgba([mix(src[0], dst[0]), mix(src[1], dst[1]), mix(src[2], dst[2]), out_alpha as u8]);
+                    img.put_pixel(x as u32, y as u32, px);
+                }
+            }
+        }
+        img
+    }
+    
+    // The frames picked by the options as images, with how long each one is shown in milliseconds.
+    pub fn render(&self, options : &Options) -> Result<Vec<(RgbaImage, u32)>, String>
+    {
+        let (first, last) = match (&options.tag, options.frame_range)
+        {
+            (Some(tag), _) => self.tags.iter().find(|(name, _, _)| name == tag).map(|&(_, from, to)| (from, to)).ok_or_else(|| format!("there is no tag called {}", tag))?,
+            (None, Some(range)) => range,
+            (None, None) => (0, self.frames.len().saturating_sub(1) as u32),
+        };
+        if first > last || last as usize >= self.frames.len()
+        {
+            return Err(format!("frames {} to {} don't exist, there are {} frames", first, last, self.frames.len()));
+        }
+        if self.layers.iter().any(|layer| layer.tilemap)
+        {
+            println!("tilemap layers aren't supported and are left out");
+        }
+        
+        let shown = self.shown_layers();
+        let layers = match &options.layer
+        {
+            Some(name) => self.layer_and_children(name)?,
+            None => (0..self.layers.len()).filter(|&i| shown[i]).collect(),
+        };
+        let layers = layers.into_iter().filter(|&i| !self.layers[i].group && !self.layers[i].tilemap).collect::<Vec<_>>();
+        
+        let mut frames = Vec::new();
+        for frame in first as usize..=last as usize
+        {
+            let img = if options.separate_layers
+            {
+                let mut img = RgbaImage::new(self.width*layers.len() as u32, self.height);
+                for (i, &layer) in layers.iter().enumerate()
+                {
+                    image::imageops::replace(&mut img, &self.draw(frame, &[layer]), i as u32*self.width, 0);
+                }
+                img
+            }
+            else
+            {
+                self.draw(frame, &layers)
+            };
+            frames.push((img, self.frames[frame].duration));
+        }
+        Ok(frames)
+    }
+}
+
+pub fn is_aseprite(filename : &str) -> bool
+{
+    let filename = filename.to_lowercase();
+    filename.ends_with(".ase") || filename.ends_with(".aseprite")
+}
@@ -0,0 +1,562 @@
+use image::Pixel;
+use image::RgbaImage;
+use core::cmp;
+use std::cell::RefCell;
+
+// Everything the modes need to know, with nothing left to guess.
+#[derive(Clone)]
+pub struct Settings
+{
+    pub mode : String,
+    pub size : u32,
+    // left, top, right, bottom
+    pub edges : (u32, u32, u32, u32),
+    pub border : u32,
+    // in tiles, with the offset already applied
+    pub origin_tile_list : Vec<(u32, u32)>,
+}
+
+// Checks that assemble() won't read outside of the input or past the tile size with these settings, since it would panic.
+pub fn check(in_img : &RgbaImage, settings : &Settings) -> Result<(), String>
+{
+    let (left, top, right, bottom) = settings.edges;
+    if left.max(top).max(right).max(bottom) > settings.size
+    {
+        return Err(format!("edges can't be more than the tile size of {}", settings.size));
+    }
+    if settings.mode == "basic_border" && settings.border > settings.size
+    {
+        return Err(format!("border can't be more than the tile size of {}", settings.size));
+    }
+    check_tiles(in_img, &settings.mode, settings.size, &settings.origin_tile_list)
+}
+
+// The part of check() that doesn't need the edges, for anything else that reads the tiles before they are known.
+pub fn check_tiles(in_img : &RgbaImage, mode : &str, size : u32, origin_tile_list : &[(u32, u32)]) -> Result<(), String>
+{
+    // the first tile of the 3x3 and 4x4 modes is the corner of a whole block of tiles
+    let block = match mode
+    {
+        "3x3" | "3x3plus" => 3,
+        "4x4" | "4x4plus" => 4,
+        _ => 1,
+    };
+    let fits = origin_tile_list.iter().enumerate().all(|(i, &(x, y))|
+    {
+        let tiles = if i == 0 { block } else { 1 };
+        (x + tiles)*size <= in_img.width() && (y + tiles)*size <= in_img.height()
+    });
+    if !fits
+    {
+        return Err("the tile list points outside of the input image".to_string());
+    }
+    Ok(())
+}
+
+// Builds a godot 3x3 minimal 12x4 tilemap out of the input tiles.
+pub fn assemble(in_img : &RgbaImage, settings : &Settings) -> RgbaImage
+{
+    let mode = settings.mode.as_str();
+    let size = settings.size;
+    let border = settings.border;
+    let (left_edge, top_edge, right_edge, bottom_edge) = settings.edges;
+    let origin_tile_list = &settings.origin_tile_list;
+    
+    let out_img = RefCell::new(image::RgbaImage::new(12*size, 4*size));
+    
+    let copy_tile_ext = |(mut x_in, mut y_in) : (u32, u32), (mut x_out, mut y_out) : (u32, u32), (min_x, min_y, mut max_x, mut max_y) : (u32, u32, u32, u32), column : Option<u32>, row : Option<u32>|
+    {
+        x_in *= size;
+        y_in *= size;
+        x_out *= size;
+        y_out *= size;
+        max_x = size-max_x;
+        max_y = size-max_y;
+        for ix in cmp::max(min_x, 0)..cmp::min(max_x, size)
+        {
+            for iy in cmp::max(min_y, 0)..cmp::min(max_y, size)
+            {
+                out_img.borrow_mut().put_pixel(x_out+ix, y_out+iy, in_img.get_pixel(x_in+column.unwrap_or(ix), y_in+row.unwrap_or(iy)).to_rgba());
+            }
+        }
+    };
+    let copy_tile_part = |a, b, c| copy_tile_ext(a, b, c, None, None);
+    let copy_tile = |a, b| copy_tile_part(a, b, (0, 0, 0, 0));
+    //let copy_column = |a, b, column| copy_tile_ext(a, b, (0, 0, 0, 0), Some(column), None);
+    //let copy_row = |a, b, row| copy_tile_ext(a, b, (0, 0, 0, 0), None, Some(row));
+    
+    let copy_tile_inplace = |(mut x_in, mut y_in) : (u32, u32), (mut x_out, mut y_out) : (u32, u32)|
+    {
+        x_in *= size;
+        y_in *= size;
+        x_out *= size;
+        y_out *= size;
+        for ix in 0..size
+        {
+            for iy in 0..size
+            {
+                let px = out_img.borrow_mut().get_pixel(x_in+ix, y_in+iy).to_rgba();
+                out_img.borrow_mut().put_pixel(x_out+ix, y_out+iy, px);
+            }
+        }
+    };
+    let clear_tile = |(mut x_out, mut y_out) : (u32, u32)|
+    {
+        x_out *= size;
+        y_out *= size;
+        for ix in 0..size
+        {
+            for iy in 0..size
+            {
+                let px = image::Rgba::from([0, 0, 0, 0]);
+                out_img.borrow_mut().put_pixel(x_out+ix, y_out+iy, px);
+            }
+        }
+    };
+    
+    let copy_4x4_to_12x4 = ||
+    {
+        copy_tile_inplace((2, 1), (5, 1));
+        copy_tile_inplace((2, 1), (5, 2));
+        copy_tile_inplace((2, 1), (6, 1));
+        copy_tile_inplace((2, 1), (6, 2));
+        
+        copy_tile_inplace((2, 1), (9, 1));
+        copy_tile_inplace((2, 1), (9, 2));
+        // blank
+        copy_tile_inplace((2, 1), (10, 2));
+        
+        copy_tile_inplace((2, 1), (9, 0));
+        copy_tile_inplace((2, 1), (11, 1));
+        copy_tile_inplace((2, 1), (8, 2));
+        copy_tile_inplace((2, 1), (10, 3));
+        
+        copy_tile_inplace((2, 1), (4, 0));
+        copy_tile_inplace((2, 1), (7, 0));
+        copy_tile_inplace((2, 1), (4, 3));
+        copy_tile_inplace((2, 1), (7, 3));
+        
+        
+        copy_tile_inplace((1, 0), (8, 0));
+        copy_tile_inplace((1, 2), (8, 3));
+        copy_tile_inplace((3, 0), (11, 0));
+        copy_tile_inplace((3, 2), (11, 3));
+        
+        copy_tile_inplace((2, 0), (5, 0));
+        copy_tile_inplace((2, 0), (6, 0));
+        copy_tile_inplace((2, 0), (10, 0));
+        
+        copy_tile_inplace((2, 2), (5, 3));
+        copy_tile_inplace((2, 2), (6, 3));
+        copy_tile_inplace((2, 2), (9, 3));
+        
+        copy_tile_inplace((1, 1), (4, 1));
+        copy_tile_inplace((1, 1), (4, 2));
+        copy_tile_inplace((1, 1), (8, 1));
+        
+        copy_tile_inplace((3, 1), (7, 1));
+        copy_tile_inplace((3, 1), (7, 2));
+        copy_tile_inplace((3, 1), (11, 2));
+        
+        clear_tile((10, 1));
+    };
+    
+    let m_left = size-right_edge;
+    let m_right = size-left_edge;
+    let m_top = size-bottom_edge;
+    let m_bottom = size-top_edge;
+    
+    let copy_corners = |corner|
+    {
+        copy_tile(corner, (2, 1));
+        
+        copy_tile_part(corner, (1, 0), (m_left, m_top, 0, 0));
+        copy_tile_part(corner, (1, 1), (m_left, 0, 0, 0));
+        copy_tile_part(corner, (1, 2), (m_left, 0, 0, m_bottom));
+        
+        copy_tile_part(corner, (2, 0), (0, m_top, 0, 0));
+        copy_tile_part(corner, (2, 2), (0, 0, 0, m_bottom));
+        
+        copy_tile_part(corner, (3, 0), (0, m_top, m_right, 0));
+        copy_tile_part(corner, (3, 1), (0, 0, m_right, 0));
+        copy_tile_part(corner, (3, 2), (0, 0, m_right, m_bottom));
+        
+        
+        copy_tile_part(corner, (4, 0), (0, m_top, m_right, 0));
+        copy_tile_part(corner, (5, 0), (0, m_top, m_right, 0));
+        copy_tile_part(corner, (5, 2), (0, m_top, m_right, 0));
+        copy_tile_part(corner, (7, 2), (0, m_top, m_right, 0));
+        
+        copy_tile_part(corner, (7, 0), (m_left, m_top, 0, 0));
+        copy_tile_part(corner, (6, 0), (m_left, m_top, 0, 0));
+        copy_tile_part(corner, (6, 2), (m_left, m_top, 0, 0));
+        copy_tile_part(corner, (4, 2), (m_left, m_top, 0, 0));
+        
+        copy_tile_part(corner, (4, 3), (0, 0, m_right, m_bottom));
+        copy_tile_part(corner, (5, 3), (0, 0, m_right, m_bottom));
+        copy_tile_part(corner, (5, 1), (0, 0, m_right, m_bottom));
+        copy_tile_part(corner, (7, 1), (0, 0, m_right, m_bottom));
+        
+        copy_tile_part(corner, (7, 3), (m_left, 0, 0, m_bottom));
+        copy_tile_part(corner, (6, 3), (m_left, 0, 0, m_bottom));
+        copy_tile_part(corner, (6, 1), (m_left, 0, 0, m_bottom));
+        copy_tile_part(corner, (4, 1), (m_left, 0, 0, m_bottom));
+        
+        
+        copy_tile_part(corner, (4, 0), (m_left, 0, 0, 0));
+        copy_tile_part(corner, (4, 3), (m_left, 0, 0, 0));
+        copy_tile_part(corner, (7, 0), (0, 0, m_right, 0));
+        copy_tile_part(corner, (7, 3), (0, 0, m_right, 0));
+        
+        copy_tile_part(corner, (8, 2), (0, 0, m_right, 0));
+        copy_tile_part(corner, (9, 0), (0, 0, 0, m_bottom));
+        copy_tile_part(corner, (10, 3), (0, m_top, 0, 0));
+        copy_tile_part(corner, (11, 1), (m_left, 0, 0, 0));
+        
+        
+        copy_tile_part(corner, (9, 1), (m_left, m_top, 0, 0));
+        copy_tile_part(corner, (9, 1), (0, 0, m_right, m_bottom));
+        
+        copy_tile_part(corner, (10, 2), (m_left, 0, 0, m_bottom));
+        copy_tile_part(corner, (10, 2), (0, m_top, m_right, 0));
+    };
+    
+    let add_coord = |a : (_, _), b : (_, _)| (a.0 + b.0, a.1 + b.1);
+    
+    match mode
+    {
+        "basic" =>
+        {
+            let tile_a = origin_tile_list[0];
+            let tile_b = origin_tile_list[1];
+            
+            for ix in 0..12
+            {
+                for iy in 0..4
+                {
+                    copy_tile(tile_a, (ix, iy));
+                }
+            }
+            
+            copy_tile(tile_b, (0, 3));
+            
+            copy_tile_part(tile_b, (0, 0), (0, 0, 0, m_bottom));
+            copy_tile_part(tile_b, (0, 2), (0, m_top, 0, 0));
+            
+            copy_tile_part(tile_b, (1, 3), (0, 0, m_right, 0));
+            copy_tile_part(tile_b, (3, 3), (m_left, 0, 0, 0));
+            
+            copy_tile_part(tile_b, (1, 0), (0, 0, m_right, m_bottom));
+            copy_tile_part(tile_b, (1, 2), (0, m_top, m_right, 0));
+            copy_tile_part(tile_b, (3, 0), (m_left, 0, 0, m_bottom));
+            copy_tile_part(tile_b, (3, 2), (m_left, m_top, 0, 0));
+            
+            copy_4x4_to_12x4();
+        }
+        "basic_border" =>
+        {
+            let tile_a = origin_tile_list[0];
+            let tile_b = origin_tile_list[1];
+            
+            let m_border = size-border;
+            let m_left_border = size-m_right;
+            let m_top_border = size-m_bottom;
+            let m_right_border = size-m_left;
+            let m_bottom_border = size-m_top;
+            
+            for ix in 0..12
+            {
+                for iy in 0..4
+                {
+                    copy_tile(tile_a, (ix, iy));
+                }
+            }
+            
+            copy_tile(tile_b, (0, 3));
+            
+            copy_tile_part(tile_b, (0, 0), (0, 0, 0, m_bottom));
+            copy_tile_part(tile_b, (0, 2), (0, m_top, 0, 0));
+            
+            copy_tile_part(tile_b, (1, 3), (0, 0, m_right, 0));
+            copy_tile_part(tile_b, (3, 3), (m_left, 0, 0, 0));
+            
+            copy_tile_part(tile_b, (1, 0), (0, 0, m_right, m_bottom));
+            copy_tile_part(tile_b, (1, 2), (0, m_top, m_right, 0));
+            copy_tile_part(tile_b, (3, 0), (m_left, 0, 0, m_bottom));
+            copy_tile_part(tile_b, (3, 2), (m_left, m_top, 0, 0));
+            
+            
+            copy_tile_ext(tile_b, (0, 0), (0, m_top_border, m_border, 0), None, Some(m_top));
+            copy_tile_ext(tile_b, (0, 0), (m_border, m_top_border, 0, 0), None, Some(m_top));
+            
+            copy_tile_ext(tile_b, (0, 1), (0, 0, m_border, 0), None, Some(m_top));
+            copy_tile_ext(tile_b, (0, 1), (m_border, 0, 0, 0), None, Some(m_top));
+            
+            copy_tile_ext(tile_b, (0, 2), (0, 0, m_border, m_bottom_border), None, Some(m_top));
+            copy_tile_ext(tile_b, (0, 2), (m_border, 0, 0, m_bottom_border), None, Some(m_top));
+            
+            
+            copy_tile_ext(tile_b, (1, 3), (m_left_border, 0, 0, m_border), Some(m_left), None);
+            copy_tile_ext(tile_b, (1, 3), (m_left_border, m_border, 0, 0), Some(m_left), None);
+            
+            copy_tile_ext(tile_b, (2, 3), (0, 0, 0, m_border), Some(m_left), None);
+            copy_tile_ext(tile_b, (2, 3), (0, m_border, 0, 0), Some(m_left), None);
+            
+            copy_tile_ext(tile_b, (3, 3), (0, 0, m_right_border, m_border), Some(m_left), None);
+            copy_tile_ext(tile_b, (3, 3), (0, m_border, m_right_border, 0), Some(m_left), None);
+            
+            
+            copy_tile_ext(tile_b, (1, 0), (m_left_border, 0, 0, m_border), Some(m_left), None);
+            copy_tile_ext(tile_b, (1, 0), (0, m_top_border, m_border, 0), None, Some(m_top));
+            
+            copy_tile_ext(tile_b, (1, 1), (0, 0, m_border, 0), None, Some(m_top));
+            
+            copy_tile_ext(tile_b, (1, 2), (0, 0, m_border, m_bottom_border), None, Some(m_top));
+            copy_tile_ext(tile_b, (1, 2), (m_left_border, m_border, 0, 0), Some(m_left), None);
+            
+            
+            copy_tile_ext(tile_b, (2, 0), (0, 0, 0, m_border), Some(m_left), None);
+            
+            copy_tile_ext(tile_b, (2, 2), (0, m_border, 0, 0), Some(m_left), None);
+            
+            
+            copy_tile_ext(tile_b, (3, 0), (0, 0, m_right_border, m_border), Some(m_left), None);
+            copy_tile_ext(tile_b, (3, 0), (m_border, m_top_border, 0, 0), None, Some(m_top));
+            
+            copy_tile_ext(tile_b, (3, 1), (m_border, 0, 0, 0), None, Some(m_top));
+            
+            copy_tile_ext(tile_b, (3, 2), (m_border, 0, 0, m_bottom_border), None, Some(m_top));
+            copy_tile_ext(tile_b, (3, 2), (0, m_border, m_right_border, 0), Some(m_left), None);
+            
+            copy_4x4_to_12x4();
+        }
+        "3x3" | "3x3plus" =>
+        {
+            let xm_left = left_edge;
+            let xm_top = top_edge;
+            let xm_right = right_edge;
+            let xm_bottom = bottom_edge;
+            
+            let tile_0_0 = origin_tile_list[0];
+            let tile_0_1 = add_coord(origin_tile_list[0], (0, 1));
+            let tile_0_2 = add_coord(origin_tile_list[0], (0, 2));
+            let tile_1_0 = add_coord(origin_tile_list[0], (1, 0));
+            let tile_1_1 = add_coord(origin_tile_list[0], (1, 1));
+            let tile_1_2 = add_coord(origin_tile_list[0], (1, 2));
+            let tile_2_0 = add_coord(origin_tile_list[0], (2, 0));
+            let tile_2_1 = add_coord(origin_tile_list[0], (2, 1));
+            let tile_2_2 = add_coord(origin_tile_list[0], (2, 2));
+            
+            for ix in 0..size*3
+            {
+                for iy in 0..size*3
+                {
+                    out_img.borrow_mut().put_pixel(ix+size, iy, in_img.get_pixel(origin_tile_list[0].0*size + ix, origin_tile_list[0].1*size + iy).to_rgba());
+                }
+            }
+            
+            copy_tile(tile_1_1, (0, 1));
+            copy_tile(tile_1_1, (0, 3));
+            copy_tile(tile_1_1, (2, 3));
+            
+            copy_tile_part(tile_1_0, (0, 3), (0, 0, 0, m_bottom));
+            copy_tile_part(tile_1_2, (0, 3), (0, m_top, 0, 0));
+            copy_tile_part(tile_0_1, (0, 3), (0, 0, m_right, 0));
+            copy_tile_part(tile_2_1, (0, 3), (m_left, 0, 0, 0));
+            
+            copy_tile_part(tile_2_2, (0, 3), (m_left, m_top, 0, 0));
+            copy_tile_part(tile_0_2, (0, 3), (0, m_top, m_right, 0));
+            copy_tile_part(tile_0_0, (0, 3), (0, 0, m_right, m_bottom));
+            copy_tile_part(tile_2_0, (0, 3), (m_left, 0, 0, m_bottom));
+            
+            copy_tile_inplace((0, 3), (0, 0));
+            copy_tile_inplace((0, 3), (0, 2));
+            copy_tile_inplace((0, 3), (1, 3));
+            copy_tile_inplace((0, 3), (3, 3));
+            copy_tile_part(tile_1_1, (0, 0), (0, xm_top, 0, 0));
+            copy_tile_part(tile_1_1, (0, 2), (0, 0, 0, xm_bottom));
+            copy_tile_part(tile_1_1, (1, 3), (xm_left, 0, 0, 0));
+            copy_tile_part(tile_1_1, (3, 3), (0, 0, xm_right, 0));
+            
+            
+            copy_tile_part(tile_2_1, (0, 0), (m_left, xm_top, 0, 0));
+            copy_tile_part(tile_0_1, (0, 0), (0, xm_top, m_right, 0));
+            
+            copy_tile_part(tile_2_1, (0, 1), (m_left, 0, 0, 0));
+            copy_tile_part(tile_0_1, (0, 1), (0, 0, m_right, 0));
+            
+            copy_tile_part(tile_2_1, (0, 2), (m_left, 0, 0, xm_bottom));
+            copy_tile_part(tile_0_1, (0, 2), (0, 0, m_right, xm_bottom));
+            
+            
+            copy_tile_part(tile_1_2, (1, 3), (xm_left, m_top, 0, 0));
+            copy_tile_part(tile_1_0, (1, 3), (xm_left, 0, 0, m_bottom));
+            
+            copy_tile_part(tile_1_2, (2, 3), (0, m_top, 0, 0));
+            copy_tile_part(tile_1_0, (2, 3), (0, 0, 0, m_bottom));
+            
+            copy_tile_part(tile_1_2, (3, 3), (0, m_top, xm_right, 0));
+            copy_tile_part(tile_1_0, (3, 3), (0, 0, xm_right, m_bottom));
+            
+            copy_tile_part(tile_2_2, (3, 3), (0, m_top, 0, 0));
+            copy_tile_part(tile_0_2, (1, 3), (0, m_top, 0, 0));
+            
+            copy_tile_part(tile_2_0, (3, 3), (0, 0, 0, m_bottom));
+            copy_tile_part(tile_0_0, (1, 3), (0, 0, 0, m_bottom));
+            
+            copy_4x4_to_12x4();
+            
+            if mode == "3x3plus"
+            {
+                copy_corners(origin_tile_list[1]);
+            }
+        }
+        "4x4" | "4x4plus" =>
+        {
+            for ix in 0..size*4
+            {
+                for iy in 0..size*4
+                {
+                    out_img.borrow_mut().put_pixel(ix, iy, in_img.get_pixel(origin_tile_list[0].0*size + ix, origin_tile_list[0].1*size + iy).to_rgba());
+                }
+            }
+            
+            copy_4x4_to_12x4();
+            
+            if mode == "4x4plus"
+            {
+                copy_corners(origin_tile_list[1]);
+            }
+        }
+        "minitiles" =>
+        {
+            let xm_left = left_edge;
+            let xm_top = top_edge;
+            let xm_right = right_edge;
+            let xm_bottom = bottom_edge;
+            
+            let tile_a = origin_tile_list[0];
+            let tile_b = origin_tile_list[1];
+            let tile_c = origin_tile_list[2];
+            let tile_x = origin_tile_list[3];
+            let tile_z = origin_tile_list[4];
+            
+            copy_tile(tile_a, (0, 3));
+            copy_tile(tile_b, (0, 1));
+            copy_tile(tile_c, (2, 3));
+            copy_tile(tile_x, (2, 1));
+            copy_tile(tile_z, (9, 2));
+            
+            copy_tile(tile_b, (0, 0));
+            copy_tile_part(tile_a, (0, 0), (0, 0, 0, m_bottom));
+            copy_tile(tile_b, (0, 2));
+            copy_tile_part(tile_a, (0, 2), (0, m_top, 0, 0));
+            
+            copy_tile(tile_c, (1, 3));
+            copy_tile_part(tile_a, (1, 3), (0, 0, m_right, 0));
+            copy_tile(tile_c, (3, 3));
+            copy_tile_part(tile_a, (3, 3), (m_left, 0, 0, 0));
+            
+            
+            copy_tile(tile_a, (1, 0));
+            copy_tile_part(tile_b, (1, 0), (0, m_top, 0, 0));
+            copy_tile_part(tile_c, (1, 0), (m_left, 0, 0, 0));
+            copy_tile_part(tile_x, (1, 0), (m_left, m_top, 0, 0));
+            
+            copy_tile(tile_c, (2, 0));
+            copy_tile_part(tile_x, (2, 0), (0, m_top, 0, 0));
+            
+            copy_tile(tile_a, (3, 0));
+            copy_tile_part(tile_b, (3, 0), (0, m_top, 0, 0));
+            copy_tile_part(tile_c, (3, 0), (0, 0, m_right, 0));
+            copy_tile_part(tile_x, (3, 0), (0, m_top, m_right, 0));
+            
+            
+            copy_tile(tile_b, (1, 1));
+            copy_tile_part(tile_x, (1, 1), (m_left, 0, 0, 0));
+            
+            copy_tile(tile_b, (3, 1));
+            copy_tile_part(tile_x, (3, 1), (0, 0, m_right, 0));
+            
+            
+            
+            copy_tile(tile_a, (1, 2));
+            copy_tile_part(tile_b, (1, 2), (0, 0, 0, m_bottom));
+            copy_tile_part(tile_c, (1, 2), (m_left, 0, 0, 0));
+            copy_tile_part(tile_x, (1, 2), (m_left, 0, 0, m_bottom));
+            
+            copy_tile(tile_c, (2, 2));
+            copy_tile_part(tile_x, (2, 2), (0, 0, 0, m_bottom));
+            
+            copy_tile(tile_a, (3, 2));
+            copy_tile_part(tile_b, (3, 2), (0, 0, 0, m_bottom));
+            copy_tile_part(tile_c, (3, 2), (0, 0, m_right, 0));
+            copy_tile_part(tile_x, (3, 2), (0, 0, m_right, m_bottom));
+            
+            
+            copy_4x4_to_12x4();
+            
+            
+            copy_tile(tile_x, (4, 0));
+            copy_tile(tile_x, (7, 0));
+            copy_tile(tile_x, (4, 3));
+            copy_tile(tile_x, (7, 3));
+            
+            copy_tile(tile_z, (5, 1));
+            copy_tile(tile_z, (6, 1));
+            copy_tile(tile_z, (5, 2));
+            copy_tile(tile_z, (6, 2));
+            
+            copy_tile_part(tile_z, (4, 0), (0, 0, xm_right, xm_bottom));
+            copy_tile_part(tile_z, (7, 0), (xm_left, 0, 0, xm_bottom));
+            copy_tile_part(tile_z, (4, 3), (0, xm_top, xm_right, 0));
+            copy_tile_part(tile_z, (7, 3), (xm_left, xm_top, 0, 0));
+            
+            copy_tile_part(tile_x, (5, 1), (0, 0, m_right, m_bottom));
+            copy_tile_part(tile_x, (6, 1), (m_left, 0, 0, m_bottom));
+            copy_tile_part(tile_x, (5, 2), (0, m_top, m_right, 0));
+            copy_tile_part(tile_x, (6, 2), (m_left, m_top, 0, 0));
+            
+            copy_tile_part(tile_z, (5, 0), (xm_left, xm_top, 0, 0));
+            copy_tile_part(tile_z, (6, 0), (0, xm_top, xm_right, 0));
+            copy_tile_part(tile_z, (5, 3), (xm_left, 0, 0, xm_bottom));
+            copy_tile_part(tile_z, (6, 3), (0, 0, xm_right, xm_bottom));
+            copy_tile_part(tile_z, (4, 1), (xm_left, xm_top, 0, 0));
+            copy_tile_part(tile_z, (7, 1), (0, xm_top, xm_right, 0));
+            copy_tile_part(tile_z, (4, 2), (xm_left, 0, 0, xm_bottom));
+            copy_tile_part(tile_z, (7, 2), (0, 0, xm_right, xm_bottom));
+            
+            
+            
+            copy_tile_part(tile_z, (8, 0), (xm_left, xm_top, 0, 0));
+            copy_tile_part(tile_z, (9, 0), (0, xm_top, 0, 0));
+            copy_tile_part(tile_z, (10, 0), (0, xm_top, 0, 0));
+            copy_tile_part(tile_z, (11, 0), (0, xm_top, xm_right, 0));
+            
+            copy_tile_part(tile_z, (8, 3), (xm_left, 0, 0, xm_bottom));
+            copy_tile_part(tile_z, (9, 3), (0, 0, 0, xm_bottom));
+            copy_tile_part(tile_z, (10, 3), (0, 0, 0, xm_bottom));
+            copy_tile_part(tile_z, (11, 3), (0, 0, xm_right, xm_bottom));
+            
+            
+            copy_tile_part(tile_z, (8, 1), (xm_left, 0, 0, 0));
+            copy_tile_part(tile_z, (8, 2), (xm_left, 0, 0, 0));
+            
+            copy_tile_part(tile_z, (11, 1), (0, 0, xm_right, 0));
+            copy_tile_part(tile_z, (11, 2), (0, 0, xm_right, 0));
+            
+            copy_tile_part(tile_z, (9, 1), (xm_left, 0, 0, xm_bottom));
+            copy_tile_part(tile_z, (9, 1), (0, xm_top, xm_right, 0));
+            
+            copy_tile_part(tile_z, (10, 2), (0, 0, xm_right, xm_bottom));
+            copy_tile_part(tile_z, (10, 2), (xm_left, xm_top, 0, 0));
+            
+            
+            copy_tile(tile_z, (9, 2));
+            clear_tile((10, 1));
+        }
+        _ => panic!("unknown mode {}", mode)
+    }
+    
+    out_img.into_inner()
+}
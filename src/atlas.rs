@@ -0,0 +1,87 @@
+use image::GenericImage;
+use image::RgbaImage;
+
+use crate::variants::VariantCell;
+
+// Where one output ended up in the atlas.
+#[derive(Default)]
+pub struct AtlasEntry
+{
+    pub name : String,
+    pub x : u32,
+    pub y : u32,
+    pub width : u32,
+    pub height : u32,
+    pub tile_size : u32,
+    // which arrangement of cells the entry uses, see masks::arrangement()
+    pub layout : String,
+    // pixels from the edge of the entry (or of each frame) to the first cell, and between cells
+    pub margin : u32,
+    pub separation : u32,
+    // cells that share their bitmask with others, relative to the entry
+    pub variants : Vec<VariantCell>,
+    // animated outputs have their frames side by side, each frame width/frames wide
+    pub frames : u32,
+    // in milliseconds
+    pub frame_duration : u32,
+}
+
+// Stacks the given outputs on top of each other, filling in where each one ended up.
+pub fn pack(outputs : Vec<(RgbaImage, AtlasEntry)>) -> (RgbaImage, Vec<AtlasEntry>)
+{
+    let width = outputs.iter().map(|(img, _)| img.width()).max().unwrap_or(0);
+    let height = outputs.iter().map(|(img, _)| img.height()).sum();
+    
+    let mut atlas = RgbaImage::new(width, height);
+    let mut entries = Vec::new();
+    let mut y = 0;
+    for (img, entry) in outputs
+    {
+        atlas.copy_from(&img, 0, y).unwrap();
+        entries.push(AtlasEntry { x : 0, y, width : img.width(), height : img.height(), ..entry });
+        y += img.height();
+    }
+    (atlas, entries)
+}
+
+pub fn json_string(text : &str) -> String
+{
+    let mut ret = "\"".to_string();
+    for c in text.chars()
+    {
+        match c
+        {
+            '"' => ret += "\\\"",
+            '\\' => ret += "\\\\",
+            '\n' => ret += "\\n",
+            c if (c as u32) < 0x20 => ret += &format!("\\u{:04x}", c as u32),
+            c => ret.push(c),
+        }
+    }
+    ret + "\""
+}
+
+// A JSON description of where each tileset is in the atlas image.
+// Animated tilesets give their number of frames and how long each one is shown.
+// Cells start margin pixels in from the edges of the tileset and are separation pixels apart, like tiled and godot describe tilesheets.
+// Tilesets with alternative cells list them (in cells, relative to the tileset) with their bitmask and the chance of being picked.
+pub fn manifest(image_filename : &str, entries : &[AtlasEntry]) -> String
+{
+    let mut ret = format!("{{\n  \"image\": {},\n  \"tilesets\": [\n", json_string(image_filename));
+    for (i, entry) in entries.iter().enumerate()
+    {
+        let variants = if entry.variants.is_empty()
+        {
+            String::new()
+        }
+        else
+        {
+            let list = entry.variants.iter().map(|variant| format!("{{\"x\": {}, \"y\": {}, \"mask\": {}, \"probability\": {}}}", variant.cell.0, variant.cell.1, variant.mask, variant.probability)).collect::<Vec<_>>();
+            format!(", \"variants\": [{}]", list.join(", "))
+        };
+        let animation = if entry.frames > 1 { format!(", \"frames\": {}, \"frame_duration_ms\": {}", entry.frames, entry.frame_duration) } else { String::new() };
+        ret += &format!("    {{\"name\": {}, \"x\": {}, \"y\": {}, \"width\": {}, \"height\": {}, \"tile_size\": {}, \"layout\": {}, \"margin\": {}, \"separation\": {}{}{}}}{}\n",
+            json_string(&entry.name), entry.x, entry.y, entry.width, entry.height, entry.tile_size, json_string(&entry.layout), entry.margin, entry.separation, animation, variants, if i+1 < entries.len() { "," } else { "" });
+    }
+    ret + "  ]\n}\n"
+}
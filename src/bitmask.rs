@@ -0,0 +1,36 @@
+use image::Rgba;
+use image::RgbaImage;
+
+// Draws each cell's bitmask on top of a copy of the sheet, godot editor style: the cell is split into 3x3 parts
+// and every bit that is set gets a red dot in the middle of its part. mask_of gives the bitmask of a cell of the layout.
+pub fn draw(sheet : &RgbaImage, size : u32, mask_of : &dyn Fn((u32, u32)) -> u16) -> RgbaImage
+{
+    let mut img = sheet.clone();
+    let dot = (size/6).max(1);
+    for cy in 0..sheet.height()/size
+    {
+        for cx in 0..sheet.width()/size
+        {
+            let mask = mask_of((cx, cy));
+            for bit in 0..9
+            {
+                if mask & (1 << bit) == 0
+                {
+                    continue;
+                }
+                // middle of the part of the cell this bit is about
+                let (bx, by) = (bit % 3, bit / 3);
+                let mid_x = cx*size + (bx*2 + 1)*size/6;
+                let mid_y = cy*size + (by*2 + 1)*size/6;
+                for y in mid_y.saturating_sub(dot/2)..(mid_y.saturating_sub(dot/2) + dot).min(img.height())
+                {
+                    for x in mid_x.saturating_sub(dot/2)..(mid_x.saturating_sub(dot/2) + dot).min(img.width())
+                    {
+                        img.put_pixel(x, y, Rgba([255, 0, 0, 255]));
+                    }
+                }
+            }
+        }
+    }
+    img
+}
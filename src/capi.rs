@@ -0,0 +1,177 @@
+// A C API for editor plugins and tools, see include/autotyler.h, which cbindgen generates from the /// comments here and
+// in src/wasm.rs. A job is made, given options the same way as on the command line, given RGBA pixels instead of file
+// names and run, and its output is read back out. Functions that can fail return 0 on success and -1 on failure, with
+// the message in autotyler_job_error().
+#![allow(clippy::missing_safety_doc)]
+
+use std::collections::HashMap;
+use std::ffi::CStr;
+use std::ffi::CString;
+use std::os::raw::c_char;
+use std::os::raw::c_int;
+use std::panic;
+use std::panic::AssertUnwindSafe;
+
+use image::RgbaImage;
+
+use crate::job::Job;
+
+/// Bumped whenever a function of the C API changes in a way that breaks callers.
+pub const API_VERSION : u32 = 1;
+
+pub struct AutotylerJob
+{
+    job : Job,
+    input : Option<RgbaImage>,
+    // images that tiles of the tile list can name instead of files
+    images : HashMap<String, RgbaImage>,
+    output : Option<RgbaImage>,
+    error : Option<CString>,
+}
+
+unsafe fn string(ptr : *const c_char) -> Result<String, String>
+{
+    if ptr.is_null()
+    {
+        return Err("got a null string".to_string());
+    }
+    CStr::from_ptr(ptr).to_str().map(|text| text.to_string()).map_err(|_| "got a string that isn't UTF-8".to_string())
+}
+
+unsafe fn image(rgba : *const u8, width : u32, height : u32) -> Result<RgbaImage, String>
+{
+    if rgba.is_null()
+    {
+        return Err("got a null pixel buffer".to_string());
+    }
+    let pixels = std::slice::from_raw_parts(rgba, width as usize*height as usize*4);
+    Ok(RgbaImage::from_raw(width, height, pixels.to_vec()).unwrap())
+}
+
+fn status(handle : &mut AutotylerJob, result : Result<(), String>) -> c_int
+{
+    match result
+    {
+        Ok(()) =>
+        {
+            handle.error = None;
+            0
+        }
+        Err(err) =>
+        {
+            handle.error = CString::new(err.replace('\0', " ")).ok();
+            -1
+        }
+    }
+}
+
+/// AUTOTYLER_API_VERSION of the library, to check against the header.
+#[no_mangle]
+pub extern "C" fn autotyler_api_version() -> u32
+{
+    API_VERSION
+}
+
+/// A job with the same defaults as the command line. It has to be freed with autotyler_job_free().
+#[no_mangle]
+pub extern "C" fn autotyler_job_new() -> *mut AutotylerJob
+{
+    Box::into_raw(Box::new(AutotylerJob { job : Job::default(), input : None, images : HashMap::new(), output : None, error : None }))
+}
+
+/// Frees a job and everything it holds. Null is ignored.
+#[no_mangle]
+pub unsafe extern "C" fn autotyler_job_free(handle : *mut AutotylerJob)
+{
+    if !handle.is_null()
+    {
+        drop(Box::from_raw(handle));
+    }
+}
+
+/// Sets a key=value option as given on the command line, like "mode" and "3x3". Options that name files are ignored by autotyler_job_run().
+#[no_mangle]
+pub unsafe extern "C" fn autotyler_job_set_option(handle : *mut AutotylerJob, key : *const c_char, value : *const c_char) -> c_int
+{
+    let handle = &mut *handle;
+    let result = string(key).and_then(|key| string(value).and_then(|value| handle.job.set_option(&key, &value)));
+    status(handle, result)
+}
+
+/// Adds an entry to the tile list as given on the command line, like "0,2", "grass:1,0" or "0,0/0,3@0.5".
+#[no_mangle]
+pub unsafe extern "C" fn autotyler_job_add_tile(handle : *mut AutotylerJob, tile : *const c_char) -> c_int
+{
+    let handle = &mut *handle;
+    let result = string(tile).and_then(|tile| handle.job.add_tile(&tile));
+    status(handle, result)
+}
+
+/// Gives the input image as width*height*4 bytes of RGBA, which are copied.
+#[no_mangle]
+pub unsafe extern "C" fn autotyler_job_set_input(handle : *mut AutotylerJob, rgba : *const u8, width : u32, height : u32) -> c_int
+{
+    let handle = &mut *handle;
+    let result = image(rgba, width, height).map(|img| handle.input = Some(img));
+    status(handle, result)
+}
+
+/// Adds an image for tiles of the tile list to name, in place of a file name.
+#[no_mangle]
+pub unsafe extern "C" fn autotyler_job_add_image(handle : *mut AutotylerJob, name : *const c_char, rgba : *const u8, width : u32, height : u32) -> c_int
+{
+    let handle = &mut *handle;
+    let result = string(name).and_then(|name| image(rgba, width, height).map(|img| { handle.images.insert(name, img); }));
+    status(handle, result)
+}
+
+/// Runs the job on the input image and any added images. A panic is caught and returned as an error instead of unwinding into the caller.
+#[no_mangle]
+pub unsafe extern "C" fn autotyler_job_run(handle : *mut AutotylerJob) -> c_int
+{
+    let handle = &mut *handle;
+    handle.output = None;
+    let run = panic::catch_unwind(AssertUnwindSafe(|| match &handle.input
+    {
+        Some(input) => handle.job.run_image(input, &handle.images),
+        None => Err("no input image has been given".to_string()),
+    }));
+    let result = match run
+    {
+        Ok(result) => result.map(|output| handle.output = Some(output)),
+        Err(payload) =>
+        {
+            let message = payload.downcast_ref::<&str>().map(|text| text.to_string()).or_else(|| payload.downcast_ref::<String>().cloned());
+            Err(format!("autotyler panicked: {}", message.unwrap_or_else(|| "no message".to_string())))
+        }
+    };
+    status(handle, result)
+}
+
+/// The output of the last successful run as width*height*4 bytes of RGBA, or null. It stays valid until the next run or until the job is freed.
+#[no_mangle]
+pub unsafe extern "C" fn autotyler_job_output(handle : *const AutotylerJob, width : *mut u32, height : *mut u32) -> *const u8
+{
+    let handle = &*handle;
+    let (w, h, pixels) = match &handle.output
+    {
+        Some(output) => (output.width(), output.height(), output.as_ptr()),
+        None => (0, 0, std::ptr::null()),
+    };
+    if !width.is_null()
+    {
+        *width = w;
+    }
+    if !height.is_null()
+    {
+        *height = h;
+    }
+    pixels
+}
+
+/// The message of the last call that failed, or null if the last call worked. It stays valid until the next call on the job.
+#[no_mangle]
+pub unsafe extern "C" fn autotyler_job_error(handle : *const AutotylerJob) -> *const c_char
+{
+    (*handle).error.as_ref().map(|err| err.as_ptr()).unwrap_or(std::ptr::null())
+}
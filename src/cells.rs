@@ -0,0 +1,76 @@
+use std::path::Path;
+
+use image::GenericImageView;
+use image::RgbaImage;
+
+use crate::atlas::json_string;
+use crate::frames;
+use crate::masks::*;
+use crate::padding::Layout;
+use crate::palette::Palette;
+use crate::variants;
+use crate::variants::VariantCell;
+
+// One cell of the output saved as its own file.
+pub struct CellFile
+{
+    // relative to the directory the cells are saved in
+    pub filename : String,
+    pub cell : (u32, u32),
+    pub mask : u16,
+    // the chance of being picked among the cells with the same bitmask
+    pub probability : f64,
+}
+
+// The neighbours in a bitmask as compass directions, clockwise from the top, like N_NE_E. Cells with no neighbours are "none".
+pub fn direction_name(mask : u16) -> String
+{
+    let directions = [(TOP, "N"), (TOP_RIGHT, "NE"), (RIGHT, "E"), (BOTTOM_RIGHT, "SE"), (BOTTOM, "S"), (BOTTOM_LEFT, "SW"), (LEFT, "W"), (TOP_LEFT, "NW")];
+    let names = directions.iter().filter(|(bit, _)| mask & bit != 0).map(|(_, name)| *name).collect::<Vec<_>>();
+    if names.is_empty() { "none".to_string() } else { names.join("_") }
+}
+
+// Saves every cell of the sheets (one sheet per frame, not padded) to its own PNG in dir, named prefix_ and then the bitmask,
+// in hex (names=hex, like grass_0x1ff) or as directions (names=directions, like grass_N_E_NE). Alternative cells
+// with the same bitmask get _2, _3 and so on after the name. Animated cells are strips of their frames. Blank cells are skipped.
+pub fn export(sheets : &[RgbaImage], layout : &Layout, variants : &[VariantCell], prefix : &str, names : &str, dir : &str, palette : Option<&Palette>) -> Result<Vec<CellFile>, String>
+{
+    std::fs::create_dir_all(dir).map_err(|err| format!("failed to create {}: {}", dir, err))?;
+    let mut files = Vec::<CellFile>::new();
+    let size = layout.size;
+    for cy in 0..sheets[0].height()/size
+    {
+        for cx in 0..sheets[0].width()/size
+        {
+            let mask = variants::mask_of(layout.arrangement, variants, (cx, cy));
+            if mask == 0
+            {
+                continue;
+            }
+            let mut name = format!("{}_{}", prefix, if names == "hex" { format!("0x{:03x}", mask) } else { direction_name(mask) });
+            let count = files.iter().filter(|file| file.mask == mask).count();
+            if count > 0
+            {
+                name += &format!("_{}", count + 1);
+            }
+            let filename = format!("{}.png", name);
+            let cell_frames = sheets.iter().map(|sheet| sheet.view(cx*size, cy*size, size, size).to_image()).collect::<Vec<_>>();
+            frames::save(&cell_frames, 0, palette, &Path::new(dir).join(&filename).to_string_lossy())?;
+            let probability = variants.iter().find(|variant| variant.cell == (cx, cy)).map(|variant| variant.probability).unwrap_or(1.0);
+            files.push(CellFile { filename, cell : (cx, cy), mask, probability });
+        }
+    }
+    Ok(files)
+}
+
+// A JSON list of the files export() saved, with the cell each one came from, its bitmask and its chance of being picked.
+pub fn manifest(files : &[CellFile], tile_size : u32, frames : u32) -> String
+{
+    let mut ret = format!("{{\n  \"tile_size\": {},\n  \"frames\": {},\n  \"tiles\": [\n", tile_size, frames);
+    for (i, file) in files.iter().enumerate()
+    {
+        ret += &format!("    {{\"file\": {}, \"x\": {}, \"y\": {}, \"mask\": {}, \"probability\": {}}}{}\n",
+            json_string(&file.filename), file.cell.0, file.cell.1, file.mask, file.probability, if i+1 < files.len() { "," } else { "" });
+    }
+    ret + "  ]\n}\n"
+}
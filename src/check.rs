@@ -0,0 +1,120 @@
+use image::Rgba;
+use image::RgbaImage;
+
+use crate::assemble;
+use crate::assemble::Settings;
+
+// A seam inside one output cell where two input tiles meet without continuing into each other.
+pub struct Discontinuity
+{
+    pub cell : (u32, u32),
+    // true if the seam runs top to bottom (the tiles are side by side)
+    pub vertical : bool,
+    // the input tiles on either side of the seam, left/top first
+    pub tiles : ((u32, u32), (u32, u32)),
+    // pairs of input pixels that end up next to each other but don't match
+    pub pixels : Vec<((u32, u32), (u32, u32))>,
+}
+
+// Input pixels are tagged with their own coordinates (plus one, so that 0 means "nothing was copied here").
+// Assembling the tagged image tells us where every output pixel was copied from.
+fn encode((x, y) : (u32, u32)) -> Rgba<u8>
+{
+    let (x, y) = (x + 1, y + 1);
+    Rgba([(x & 0xff) as u8, (x >> 8) as u8, (y & 0xff) as u8, (y >> 8) as u8])
+}
+
+pub(crate) fn decode(px : &Rgba<u8>) -> Option<(u32, u32)>
+{
+    let x = px[0] as u32 | (px[1] as u32) << 8;
+    let y = px[2] as u32 | (px[3] as u32) << 8;
+    if x == 0 || y == 0 { None } else { Some((x - 1, y - 1)) }
+}
+
+// Assembles an input of the given size made of tagged pixels, see decode().
+pub(crate) fn trace((width, height) : (u32, u32), settings : &Settings) -> Result<RgbaImage, String>
+{
+    if width >= 0xffff || height >= 0xffff
+    {
+        return Err(format!("{}x{} is too large to trace", width, height));
+    }
+    let tagged = RgbaImage::from_fn(width, height, |x, y| encode((x, y)));
+    Ok(assemble::assemble(&tagged, settings))
+}
+
+// Finds every place where autotyler stitches two parts of the input together in a way that shows.
+// A seam between input pixels a and b (b right of or below a in the output) shows if neither the pixel
+// that follows a in its own tile matches b, nor the pixel before b in its own tile matches a.
+pub fn check(in_img : &RgbaImage, settings : &Settings) -> Result<Vec<Discontinuity>, String>
+{
+    let size = settings.size;
+    // basic_border draws its borders by shifting the edge tile around, so its seams aren't stitched from the input
+    if settings.mode == "basic_border"
+    {
+        return Err("basic_border generates its own seams, check the input with mode=basic instead".to_string());
+    }
+    let sources = trace(in_img.dimensions(), settings)?;
+    
+    let tile_of = |(x, y) : (u32, u32)| (x/size, y/size);
+    let same_tile = |a : (u32, u32), b : (u32, u32)| tile_of(a) == tile_of(b) && b.0 < in_img.width() && b.1 < in_img.height();
+    
+    let mut found = Vec::<Discontinuity>::new();
+    for (x, y, px) in sources.enumerate_pixels()
+    {
+        for &(dx, dy) in &[(1, 0), (0, 1)]
+        {
+            let (nx, ny) = (x + dx, y + dy);
+            // seams between cells are --verify's business
+            if nx/size != x/size || ny/size != y/size || nx >= sources.width() || ny >= sources.height()
+            {
+                continue;
+            }
+            let (a, b) = match (decode(px), decode(sources.get_pixel(nx, ny)))
+            {
+                (Some(a), Some(b)) => (a, b),
+                _ => continue,
+            };
+            let after_a = (a.0 + dx, a.1 + dy);
+            // continuing normally, or stretching a single row or column
+            if b == after_a || b == a
+            {
+                continue;
+            }
+            let flows_from_a = same_tile(a, after_a) && in_img.get_pixel(after_a.0, after_a.1) == in_img.get_pixel(b.0, b.1);
+            let flows_into_b = b.0 >= dx && b.1 >= dy && same_tile(b, (b.0 - dx, b.1 - dy)) && in_img.get_pixel(b.0 - dx, b.1 - dy) == in_img.get_pixel(a.0, a.1);
+            if flows_from_a || flows_into_b
+            {
+                continue;
+            }
+            
+            let cell = (x/size, y/size);
+            let vertical = dx == 1;
+            let tiles = (tile_of(a), tile_of(b));
+            match found.iter_mut().find(|d| d.cell == cell && d.vertical == vertical && d.tiles == tiles)
+            {
+                Some(discontinuity) => discontinuity.pixels.push((a, b)),
+                None => found.push(Discontinuity { cell, vertical, tiles, pixels : vec!((a, b)) }),
+            }
+        }
+    }
+    Ok(found)
+}
+
+// A faded copy of the input with every pixel involved in a discontinuity in red.
+pub fn overlay(in_img : &RgbaImage, found : &[Discontinuity]) -> RgbaImage
+{
+    let mut img = RgbaImage::from_fn(in_img.width(), in_img.height(), |x, y|
+    {
+        let px = in_img.get_pixel(x, y);
+        Rgba([px[0]/3, px[1]/3, px[2]/3, px[3]])
+    });
+    for discontinuity in found
+    {
+        for &(a, b) in &discontinuity.pixels
+        {
+            img.put_pixel(a.0, a.1, Rgba([255, 0, 0, 255]));
+            img.put_pixel(b.0, b.1, Rgba([255, 0, 0, 255]));
+        }
+    }
+    img
+}
@@ -0,0 +1,253 @@
+use image::RgbaImage;
+
+#[derive(Clone, Copy)]
+enum Side
+{
+    Left,
+    Top,
+    Right,
+    Bottom,
+}
+
+// pixel position of the i-th line (counted from the given side) at position j along the line
+fn line_pixel(side : Side, size : u32, i : u32, j : u32) -> (u32, u32)
+{
+    match side
+    {
+        Side::Left => (i, j),
+        Side::Top => (j, i),
+        Side::Right => (size-1-i, j),
+        Side::Bottom => (j, size-1-i),
+    }
+}
+
+fn line_matches(img : &RgbaImage, size : u32, side : Side, i : u32, band : (u32, u32), edge_tile : (u32, u32), fill_tile : (u32, u32)) -> bool
+{
+    (band.0..band.1).all(|j|
+    {
+        let (x, y) = line_pixel(side, size, i, j);
+        img.get_pixel(edge_tile.0*size + x, edge_tile.1*size + y) == img.get_pixel(fill_tile.0*size + x, fill_tile.1*size + y)
+    })
+}
+
+fn line_opaque(img : &RgbaImage, size : u32, side : Side, i : u32, band : (u32, u32), tile : (u32, u32)) -> bool
+{
+    (band.0..band.1).all(|j|
+    {
+        let (x, y) = line_pixel(side, size, i, j);
+        img.get_pixel(tile.0*size + x, tile.1*size + y)[3] == 255
+    })
+}
+
+// Measures how far an edge reaches into a tile from the given side.
+// Uses the start of the longest run of lines (from the outside in) where the edge tile matches the fill tile, so that
+// lines that only match by chance don't count. If nothing matches, uses the first line where the edge tile becomes
+// opaque, as long as the fill tile is opaque. Returns None if neither gives an answer strictly inside the tile.
+fn detect_edge(img : &RgbaImage, size : u32, side : Side, band : (u32, u32), edge_tile : (u32, u32), fill_tile : (u32, u32)) -> Option<u32>
+{
+    let mut best = None;
+    let mut best_len = 0;
+    let mut run_start = 0;
+    let mut run_len = 0;
+    for i in 0..size
+    {
+        if line_matches(img, size, side, i, band, edge_tile, fill_tile)
+        {
+            if run_len == 0
+            {
+                run_start = i;
+            }
+            run_len += 1;
+            if run_len > best_len
+            {
+                best = Some(run_start);
+                best_len = run_len;
+            }
+        }
+        else
+        {
+            run_len = 0;
+        }
+    }
+    if best.is_none() && (0..size).all(|i| line_opaque(img, size, side, i, band, fill_tile))
+    {
+        best = (0..size).find(|&i| line_opaque(img, size, side, i, band, edge_tile));
+    }
+    best.filter(|&i| i > 0)
+}
+
+// Which sides of each tile of a 3x3 block have edges, as [left, top, right, bottom] by [y][x].
+const BLOCK_SIDES : [[[bool; 4]; 3]; 3] = [
+    [[true, true, false, false], [false, true, false, false], [false, true, true, false]],
+    [[true, false, false, false], [false, false, false, false], [false, false, true, false]],
+    [[true, false, false, true], [false, false, false, true], [false, false, true, true]],
+];
+
+// Whether every pixel where a tile with edges differs from the fill tile is inside the edges on the sides it has them on.
+// If not, the edges cut through what the tiles draw there, usually because a line of an edge tile only matched the fill
+// tile by chance, like a one pixel outline in front of a fill of one flat colour.
+fn edges_cover_tiles(img : &RgbaImage, size : u32, (left, top, right, bottom) : (u32, u32, u32, u32), fill : (u32, u32), tiles : &[((u32, u32), [bool; 4])]) -> bool
+{
+    tiles.iter().all(|&(tile, sides)| (0..size).all(|y| (0..size).all(|x|
+    {
+        let inside = (sides[0] && x < left) || (sides[1] && y < top) || (sides[2] && x >= size-right) || (sides[3] && y >= size-bottom);
+        inside || img.get_pixel(tile.0*size + x, tile.1*size + y) == img.get_pixel(fill.0*size + x, fill.1*size + y)
+    })))
+}
+
+// Picks left, top, right and bottom edge sizes for the given mode by comparing its edge tiles against its fill tile.
+// Edges that can't be detected are derived from the opposite edge, or fall back to half the tile size. If the edges
+// found that way would cut through the corner or edge tiles, they all fall back to half the tile size.
+pub fn detect_edges(img : &RgbaImage, mode : &str, origin_tile_list : &[(u32, u32)], size : u32) -> (u32, u32, u32, u32)
+{
+    let add_coord = |a : (u32, u32), b : (u32, u32)| (a.0 + b.0, a.1 + b.1);
+    let full = (0, size);
+    let block = |o : (u32, u32)| (0..3).flat_map(|y| (0..3).map(move |x| (x, y))).map(|(x, y)| (add_coord(o, (x, y)), BLOCK_SIDES[y as usize][x as usize])).collect::<Vec<_>>();
+    
+    // (fill tile, left, top, right, bottom edge tiles, band of each line to compare, every tile with edges and its sides)
+    let (fill, left, top, right, bottom, band, tiles) = match mode
+    {
+        "3x3" | "3x3plus" =>
+        {
+            let o = origin_tile_list[0];
+            (add_coord(o, (1, 1)), add_coord(o, (0, 1)), add_coord(o, (1, 0)), add_coord(o, (2, 1)), add_coord(o, (1, 2)), full, block(o))
+        }
+        "4x4" | "4x4plus" =>
+        {
+            // a 3x3 block, with the tiles for one tile wide strips down its left and along its bottom
+            let o = origin_tile_list[0];
+            let mut tiles = block(add_coord(o, (1, 0)));
+            tiles.extend_from_slice(&[
+                (o, [true, true, true, false]),
+                (add_coord(o, (0, 1)), [true, false, true, false]),
+                (add_coord(o, (0, 2)), [true, false, true, true]),
+                (add_coord(o, (0, 3)), [true, true, true, true]),
+                (add_coord(o, (1, 3)), [true, true, false, true]),
+                (add_coord(o, (2, 3)), [false, true, false, true]),
+                (add_coord(o, (3, 3)), [false, true, true, true]),
+            ]);
+            (add_coord(o, (2, 1)), add_coord(o, (1, 1)), add_coord(o, (2, 0)), add_coord(o, (3, 1)), add_coord(o, (2, 2)), full, tiles)
+        }
+        "minitiles" =>
+        {
+            let t = origin_tile_list;
+            let tiles = vec!((t[0], [true, true, true, true]), (t[1], [true, false, true, false]), (t[2], [false, true, false, true]));
+            (t[4], t[1], t[2], t[1], t[2], full, tiles)
+        }
+        // the second tile has edges on every side, so only compare the middle of each line
+        _ =>
+        {
+            let t = origin_tile_list;
+            (t[0], t[1], t[1], t[1], t[1], (size/4, size-size/4), vec!((t[1], [true, true, true, true])))
+        }
+    };
+    
+    let left_edge = detect_edge(img, size, Side::Left, band, left, fill);
+    let top_edge = detect_edge(img, size, Side::Top, band, top, fill);
+    let right_edge = detect_edge(img, size, Side::Right, band, right, fill);
+    let bottom_edge = detect_edge(img, size, Side::Bottom, band, bottom, fill);
+    
+    if left_edge.is_none() || top_edge.is_none() || right_edge.is_none() || bottom_edge.is_none()
+    {
+        println!("could not reliably detect all edges, falling back to half the tile size where ambiguous");
+    }
+    
+    let pick = |a : Option<u32>, b : Option<u32>| a.unwrap_or_else(|| b.map(|b| size-b).unwrap_or(size/2));
+    let pick_far = |a : Option<u32>, b : Option<u32>| a.unwrap_or_else(|| size-b.unwrap_or(size/2));
+    let edges = (pick(left_edge, right_edge), pick(top_edge, bottom_edge), pick_far(right_edge, left_edge), pick_far(bottom_edge, top_edge));
+    if !edges_cover_tiles(img, size, edges, fill, &tiles)
+    {
+        println!("edges of {},{},{},{} would cut through the edge tiles, falling back to half the tile size", edges.0, edges.1, edges.2, edges.3);
+        return (size/2, size/2, size-size/2, size-size/2);
+    }
+    edges
+}
+
+// mode, width in tiles, height in tiles, cells that must be empty
+type InputShape = (&'static str, u32, u32, &'static [(u32, u32)]);
+
+// every input shape the modes accept
+const INPUT_SHAPES : &[InputShape] = &[
+    ("basic", 2, 1, &[]),
+    ("basic", 1, 2, &[]),
+    ("3x3", 3, 3, &[]),
+    ("3x3plus", 4, 3, &[(3, 1), (3, 2)]),
+    ("4x4", 4, 4, &[]),
+    ("4x4plus", 5, 4, &[(4, 1), (4, 2), (4, 3)]),
+    ("minitiles", 5, 1, &[]),
+];
+
+// The width and height in tiles of the usual input image for a mode.
+pub fn input_shape(mode : &str) -> Option<(u32, u32)>
+{
+    let mode = if mode == "basic_border" { "basic" } else { mode };
+    INPUT_SHAPES.iter().find(|shape| shape.0 == mode).map(|shape| (shape.1, shape.2))
+}
+
+fn tile_is_empty(img : &RgbaImage, size : u32, (x, y) : (u32, u32)) -> bool
+{
+    (0..size).all(|iy| (0..size).all(|ix| img.get_pixel(x*size + ix, y*size + iy)[3] == 0))
+}
+
+// Guesses the mode and tile size of an input image from its dimensions, using the known input shapes.
+// Shapes are checked against the transparent gutters in the image: every tile a shape uses must have something in it,
+// and the unused cells of the plus shapes must be fully transparent.
+// If several shapes fit but only one of them has a power-of-two tile size, that one is picked.
+// Returns an error message if no shape fits or if several different ones still do.
+pub fn detect_layout(img : &RgbaImage, known_mode : Option<&str>, known_size : Option<u32>) -> Result<(String, u32), String>
+{
+    let (width, height) = img.dimensions();
+    // basic_border takes the same input as basic
+    let known_shape_mode = known_mode.map(|mode| if mode == "basic_border" { "basic" } else { mode });
+    
+    let mut candidates = Vec::<(&str, u32)>::new();
+    for &(mode, columns, rows, empty) in INPUT_SHAPES
+    {
+        if known_shape_mode.is_some() && known_shape_mode != Some(mode)
+        {
+            continue;
+        }
+        if width % columns != 0 || height % rows != 0 || width/columns != height/rows || width == 0
+        {
+            continue;
+        }
+        let size = width/columns;
+        if known_size.is_some() && known_size != Some(size)
+        {
+            continue;
+        }
+        let fits = (0..columns).all(|x| (0..rows).all(|y| tile_is_empty(img, size, (x, y)) == empty.contains(&(x, y))));
+        if fits && !candidates.contains(&(mode, size))
+        {
+            candidates.push((mode, size));
+        }
+    }
+    
+    let power_of_two = candidates.iter().filter(|(_, size)| size.is_power_of_two()).copied().collect::<Vec<_>>();
+    if candidates.len() > 1 && power_of_two.len() == 1
+    {
+        candidates = power_of_two;
+    }
+    
+    match candidates.len()
+    {
+        1 =>
+        {
+            let (mode, size) = candidates[0];
+            Ok((known_mode.unwrap_or(mode).to_string(), size))
+        }
+        0 =>
+        {
+            if width*3 == height*2
+            {
+                return Err(format!("{}x{} looks like an RPG Maker autotile block, which autotyler has no mode for", width, height));
+            }
+            Err(format!("could not guess the layout of a {}x{} image, please specify mode= and size=", width, height))
+        }
+        _ =>
+        {
+            let list = candidates.iter().map(|(mode, size)| format!("mode={} size={}", mode, size)).collect::<Vec<_>>().join(", ");
+            Err(format!("the layout of a {}x{} image is ambiguous ({}), please specify mode= or size=", width, height, list))
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use std::fs::File;
+use std::io::BufReader;
+
+use image::AnimationDecoder;
+use image::GenericImage;
+use image::GenericImageView;
+use image::RgbaImage;
+
+use crate::aseprite;
+use crate::palette;
+
+fn is_gif(filename : &str) -> bool
+{
+    filename.to_lowercase().ends_with(".gif")
+}
+
+// Loads every frame of an image, with the delay of the first frame in milliseconds for animated GIFs and Aseprite files.
+// Anything else is a single frame. Aseprite files are read as the options say.
+pub fn load(filename : &str, options : &aseprite::Options) -> Result<(Vec<RgbaImage>, Option<u32>), String>
+{
+    let fail = |err : image::ImageError| format!("failed to open {}: {}", filename, err);
+    if aseprite::is_aseprite(filename)
+    {
+        let data = std::fs::read(filename).map_err(|err| format!("failed to open {}: {}", filename, err))?;
+        let frames = aseprite::Aseprite::read(&data).and_then(|ase| ase.render(options)).map_err(|err| format!("failed to read {}: {}", filename, err))?;
+        let delay = frames.first().map(|frame| frame.1);
+        return Ok((frames.into_iter().map(|frame| frame.0).collect(), delay));
+    }
+    if !is_gif(filename)
+    {
+        return Ok((vec!(image::open(filename).map_err(fail)?.to_rgba()), None));
+    }
+    let file = File::open(filename).map_err(|err| format!("failed to open {}: {}", filename, err))?;
+    let frames = image::gif::GifDecoder::new(BufReader::new(file)).map_err(fail)?.into_frames().collect_frames().map_err(fail)?;
+    let delay = frames.first().map(|frame|
+    {
+        let (numerator, denominator) = frame.delay().numer_denom_ms();
+        numerator/denominator.max(1)
+    });
+    if frames.is_empty()
+    {
+        return Err(format!("{} has no frames", filename));
+    }
+    Ok((frames.into_iter().map(|frame| frame.into_buffer()).collect(), delay))
+}
+
+// Cuts an image into the given number of equally sized frames, side by side or on top of each other.
+pub fn split(img : &RgbaImage, count : u32, vertical : bool) -> Result<Vec<RgbaImage>, String>
+{
+    let (width, height) = img.dimensions();
+    let length = if vertical { height } else { width };
+    if count == 0 || length % count != 0
+    {
+        return Err(format!("a {}x{} image can't be split into {} frames", width, height, count));
+    }
+    let step = length/count;
+    Ok((0..count).map(|i|
+    {
+        if vertical
+        {
+            img.view(0, i*step, width, step).to_image()
+        }
+        else
+        {
+            img.view(i*step, 0, step, height).to_image()
+        }
+    }).collect())
+}
+
+// Puts the output of every frame side by side.
+pub fn strip(sheets : &[RgbaImage]) -> RgbaImage
+{
+    let width = sheets.iter().map(|sheet| sheet.width()).sum();
+    let height = sheets.iter().map(|sheet| sheet.height()).max().unwrap_or(0);
+    let mut img = RgbaImage::new(width, height);
+    let mut x = 0;
+    for sheet in sheets
+    {
+        img.copy_from(sheet, x, 0).unwrap();
+        x += sheet.width();
+    }
+    img
+}
+
+// Saves the frames as a looping GIF. GIFs only have one bit of transparency and 256 colours per frame.
+fn save_gif(sheets : &[RgbaImage], delay : u32, filename : &str) -> Result<(), std::io::Error>
+{
+    let (width, height) = sheets[0].dimensions();
+    let mut encoder = gif::Encoder::new(File::create(filename)?, width as u16, height as u16, &[])?;
+    encoder.write_extension(gif::ExtensionData::Repetitions(gif::Repeat::Infinite))?;
+    for sheet in sheets
+    {
+        let mut pixels = sheet.clone().into_raw();
+        let mut frame = gif::Frame::from_rgba_speed(width as u16, height as u16, &mut pixels, 10);
+        // in hundredths of a second
+        frame.delay = (delay/10) as u16;
+        frame.dispose = gif::DisposalMethod::Background;
+        encoder.write_frame(&frame)?;
+    }
+    Ok(())
+}
+
+// Saves the frames as the given file: an animated GIF if it ends in .gif, or else a horizontal strip.
+// PNGs are indexed with the palette if there is one and every colour is in it.
+pub fn save(sheets : &[RgbaImage], delay : u32, palette : Option<&palette::Palette>, filename : &str) -> Result<(), String>
+{
+    if sheets.len() > 1 && is_gif(filename)
+    {
+        let (width, height) = sheets[0].dimensions();
+        if width > 0xffff || height > 0xffff
+        {
+            return Err(format!("{}x{} is too large for a GIF", width, height));
+        }
+        return save_gif(sheets, delay, filename).map_err(|err| format!("failed to save {}: {}", filename, err));
+    }
+    let img = strip(sheets);
+    if let Some(palette) = palette.filter(|_| filename.to_lowercase().ends_with(".png"))
+    {
+        if palette::save(&img, palette, filename)?
+        {
+            return Ok(());
+        }
+        println!("{} has colours that aren't in the input palette (or needs a transparent entry the full palette has no room for), saving it as RGBA", filename);
+    }
+    img.save(filename).map_err(|err| format!("failed to save {}: {}", filename, err))
+}
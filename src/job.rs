@@ -0,0 +1,916 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::path::Path;
+
+use image::GenericImage;
+use image::RgbaImage;
+
+use crate::aseprite;
+use crate::assemble;
+use crate::assemble::Settings;
+use crate::atlas;
+use crate::bitmask;
+use crate::cells;
+use crate::detect;
+use crate::frames;
+use crate::ldtk;
+use crate::masks;
+use crate::padding;
+use crate::palette;
+use crate::preview;
+use crate::scale;
+use crate::unity;
+use crate::variants;
+use crate::variants::VariantCell;
+
+#[derive(Clone, PartialEq)]
+pub enum Edges
+{
+    // half the tile size
+    Default,
+    Auto,
+    // LEFT,TOP,RIGHT,BOTTOM | LEFT,TOP | TOP, as given on the command line
+    Measures(Vec<u32>),
+}
+
+impl Edges
+{
+    // auto | LEFT,TOP,RIGHT,BOTTOM | LEFT,TOP | TOP
+    pub fn parse(value : &str) -> Result<Edges, String>
+    {
+        if value == "auto"
+        {
+            return Ok(Edges::Auto);
+        }
+        Ok(Edges::Measures(value.splitn(4, ',').map(parse_number).collect::<Result<Vec<_>, _>>()?))
+    }
+    
+    // Left, top, right and bottom edges for the given tile size, or None for Auto, which needs the input.
+    pub fn measures(&self, size : u32) -> Option<(u32, u32, u32, u32)>
+    {
+        let half = size/2;
+        match self
+        {
+            Edges::Default => Some((half, half, size-half, size-half)),
+            Edges::Auto => None,
+            Edges::Measures(dims) if dims.len() >= 4 => Some((dims[0], dims[1], dims[2], dims[3])),
+            Edges::Measures(dims) if dims.len() >= 2 => Some((dims[0], dims[1], size.saturating_sub(dims[0]), size.saturating_sub(dims[1]))),
+            Edges::Measures(dims) => Some((half, dims[0], size-half, size.saturating_sub(dims[0]))),
+        }
+    }
+}
+
+// One input image turned into one output image, with options as given by the user.
+// Options that depend on each other (like size and edges) are only combined in settings(), so the order they are given in doesn't matter.
+#[derive(Clone)]
+pub struct Job
+{
+    // what the job is called in atlas manifests
+    pub name : String,
+    pub in_filename : String,
+    // more input images, one frame each
+    pub frame_filenames : Vec<String>,
+    pub out_filename : String,
+    pub mode : String,
+    // None if size=auto
+    pub size : Option<u32>,
+    pub edges : Edges,
+    pub offset : (u32, u32),
+    pub border : u32,
+    // pixels of extruded edge around every output cell, and of empty space around the whole output
+    pub padding : u32,
+    pub margin : u32,
+    // which cell of the output goes where
+    pub layout : &'static masks::Arrangement,
+    // what the output is scaled up by, and how: nearest or scale2x
+    pub scale : u32,
+    pub scaler : String,
+    pub origin_tile_list : Vec<(u32, u32)>,
+    // how many frames the input image holds, side by side or on top of each other
+    pub frames : u32,
+    pub frames_vertical : bool,
+    // in milliseconds, None for the delay of an animated GIF input or 100
+    pub frame_duration : Option<u32>,
+    // which layers and frames to use from Aseprite files
+    pub aseprite : aseprite::Options,
+    // every tile of each entry of the tile list, the first one being the one in origin_tile_list
+    pub tile_variants : Vec<Vec<TileRef>>,
+    // empty if there is no preview
+    pub preview_filename : String,
+    // ASCII or image map to draw the preview with, empty for the built-in one
+    pub map_filename : String,
+    // empty if there is no bitmask overlay
+    pub bitmask_filename : String,
+    // directory to save every cell to as its own file, empty if not, and whether their names give the bitmask in hex or as directions
+    pub cells_dir : String,
+    pub cell_names : String,
+    // RuleTile asset to write for unity, empty if none
+    pub unity_filename : String,
+    // LDtk tileset and rule definitions to write, empty if none, and the IntGrid value the rules look for
+    pub ldtk_filename : String,
+    pub ldtk_value : u32,
+    // whether outputs of an indexed PNG input are saved with its palette
+    pub keep_palette : bool,
+}
+
+impl Default for Job
+{
+    fn default() -> Job
+    {
+        Job
+        {
+            name : String::new(),
+            in_filename : String::new(),
+            frame_filenames : Vec::new(),
+            out_filename : String::new(),
+            mode : "basic".to_string(),
+            size : Some(16),
+            edges : Edges::Default,
+            offset : (0, 0),
+            border : 2,
+            padding : 0,
+            margin : 0,
+            layout : &masks::GODOT,
+            scale : 1,
+            scaler : "nearest".to_string(),
+            origin_tile_list : Vec::new(),
+            frames : 1,
+            frames_vertical : false,
+            frame_duration : None,
+            aseprite : aseprite::Options::default(),
+            tile_variants : Vec::new(),
+            preview_filename : String::new(),
+            map_filename : String::new(),
+            bitmask_filename : String::new(),
+            cells_dir : String::new(),
+            cell_names : "hex".to_string(),
+            unity_filename : String::new(),
+            ldtk_filename : String::new(),
+            ldtk_value : 1,
+            keep_palette : false,
+        }
+    }
+}
+
+fn parse_number(text : &str) -> Result<u32, String>
+{
+    text.trim().parse::<u32>().map_err(|_| format!("expected a number, got '{}'", text))
+}
+
+// One tile of the tile list.
+#[derive(Clone)]
+pub struct TileRef
+{
+    // the image to take the tile from, empty for the input image
+    pub source : String,
+    pub tile : (u32, u32),
+    // how often it's picked out of a group of alternatives
+    pub weight : f64,
+}
+
+// [FILE:]X,Y[@WEIGHT]
+fn parse_tile_ref(text : &str) -> Result<TileRef, String>
+{
+    let (source, rest) = match text.rfind(':')
+    {
+        Some(i) => (&text[..i], &text[i+1..]),
+        None => ("", text),
+    };
+    let parse = rest.splitn(2, '@').collect::<Vec<_>>();
+    let weight = match parse.get(1)
+    {
+        Some(weight) => weight.trim().parse::<f64>().ok().filter(|weight| *weight >= 0.0).ok_or_else(|| format!("expected a weight, got '{}'", weight))?,
+        None => 1.0,
+    };
+    Ok(TileRef { source : source.to_string(), tile : parse_coord(parse[0])?, weight })
+}
+
+fn parse_coord(text : &str) -> Result<(u32, u32), String>
+{
+    let dims = text.splitn(2, ',').collect::<Vec<_>>();
+    if dims.len() != 2
+    {
+        return Err(format!("expected two numbers separated by a comma, got '{}'", text));
+    }
+    Ok((parse_number(dims[0])?, parse_number(dims[1])?))
+}
+
+impl Job
+{
+    // Applies one key=value option.
+    pub fn set_option(&mut self, key : &str, value : &str) -> Result<(), String>
+    {
+        match key
+        {
+            "size" => self.size = if value == "auto" { None } else { Some(parse_number(value)?) },
+            "edges" => self.edges = Edges::parse(value)?,
+            "offset" => self.offset = parse_coord(value)?,
+            "gap" => return Err("gap not implemented yet".to_string()),
+            "mode" => self.mode = value.to_string(),
+            "border" => self.border = parse_number(value)?,
+            "padding" => self.padding = parse_number(value)?,
+            "margin" => self.margin = parse_number(value)?,
+            "layout" => self.layout = masks::arrangement(value).ok_or_else(|| format!("layout must be godot, blob or blob_packed, got '{}'", value))?,
+            "scale" => self.scale = parse_number(value)?,
+            "scaler" =>
+            {
+                if value != "nearest" && value != "scale2x"
+                {
+                    return Err(format!("scaler must be nearest or scale2x, got '{}'", value));
+                }
+                self.scaler = value.to_string();
+            }
+            "preview" => self.preview_filename = value.to_string(),
+            "map" => self.map_filename = value.to_string(),
+            "bitmask" => self.bitmask_filename = value.to_string(),
+            "cells" => self.cells_dir = value.to_string(),
+            "unity" => self.unity_filename = value.to_string(),
+            "ldtk" => self.ldtk_filename = value.to_string(),
+            "ldtk_value" => self.ldtk_value = parse_number(value)?,
+            "cell_names" =>
+            {
+                if value != "hex" && value != "directions"
+                {
+                    return Err(format!("cell_names must be hex or directions, got '{}'", value));
+                }
+                self.cell_names = value.to_string();
+            }
+            "frames" =>
+            {
+                self.frames = parse_number(value)?;
+                if self.frames == 0
+                {
+                    return Err("frames must be at least 1".to_string());
+                }
+            }
+            "frame_layout" =>
+            {
+                self.frames_vertical = match value
+                {
+                    "horizontal" => false,
+                    "vertical" => true,
+                    _ => return Err(format!("frame_layout must be horizontal or vertical, got '{}'", value)),
+                };
+            }
+            "frame" => self.frame_filenames.push(value.to_string()),
+            "frame_duration" => self.frame_duration = Some(parse_number(value)?),
+            "layer" => self.aseprite.layer = Some(value.to_string()),
+            "tag" => self.aseprite.tag = Some(value.to_string()),
+            "frame_range" =>
+            {
+                let range = value.splitn(2, '-').map(parse_number).collect::<Result<Vec<_>, _>>()?;
+                self.aseprite.frame_range = Some((range[0], *range.get(1).unwrap_or(&range[0])));
+            }
+            "layers" =>
+            {
+                self.aseprite.separate_layers = match value
+                {
+                    "merged" => false,
+                    "separate" => true,
+                    _ => return Err(format!("layers must be merged or separate, got '{}'", value)),
+                };
+            }
+            "palette" =>
+            {
+                self.keep_palette = match value
+                {
+                    "keep" => true,
+                    "rgba" => false,
+                    _ => return Err(format!("palette must be keep or rgba, got '{}'", value)),
+                };
+            }
+            _ => return Err(format!("unsupported option {}", key)),
+        }
+        Ok(())
+    }
+    
+    // Adds an entry to the tile list: X,Y or FILE:X,Y, or a group of alternatives like X,Y/FILE:X,Y@WEIGHT/X,Y.
+    pub fn add_tile(&mut self, text : &str) -> Result<(), String>
+    {
+        let mut group = Vec::new();
+        // slashes can also be part of file names, so only split where the part before them is a whole tile
+        let mut pending = None::<String>;
+        for part in text.split('/')
+        {
+            let joined = match pending.take()
+            {
+                Some(before) => format!("{}/{}", before, part),
+                None => part.to_string(),
+            };
+            match parse_tile_ref(&joined)
+            {
+                Ok(tile) => group.push(tile),
+                Err(_) => pending = Some(joined),
+            }
+        }
+        if let Some(text) = pending
+        {
+            parse_tile_ref(&text)?;
+        }
+        self.origin_tile_list.push(group[0].tile);
+        self.tile_variants.push(group);
+        Ok(())
+    }
+    
+    // The tile groups of the tile list with the offset applied, as (tile, weight).
+    pub fn variant_groups(&self) -> Vec<Vec<((u32, u32), f64)>>
+    {
+        self.tile_variants.iter().map(|group| group.iter().map(|tile| ((tile.tile.0 + self.offset.0, tile.tile.1 + self.offset.1), tile.weight)).collect()).collect()
+    }
+    
+    // Every image other than the input that the tile list takes tiles from.
+    pub fn tile_sources(&self) -> Vec<String>
+    {
+        let mut sources = self.tile_variants.iter().flatten().map(|tile| tile.source.clone()).filter(|source| !source.is_empty()).collect::<Vec<_>>();
+        sources.sort();
+        sources.dedup();
+        sources
+    }
+    
+    // Puts every image the tile list takes tiles from to the right of the input image, lined up with the tile grid,
+    // and returns that image with a copy of the job whose tile list points into it. The offset only applies to the
+    // tiles of the input image itself. sources has to have every image in tile_sources().
+    pub fn with_tile_sources(&self, in_img : &RgbaImage, sources : &HashMap<String, RgbaImage>) -> Result<(Job, RgbaImage), String>
+    {
+        let filenames = self.tile_sources();
+        if filenames.is_empty()
+        {
+            return Ok((self.clone(), in_img.clone()));
+        }
+        
+        // the tile size has to be known to line up the images, so guess it from the input image now if needed
+        let mut job = self.clone();
+        if job.size.is_none() || job.mode == "auto"
+        {
+            let known_mode = if job.mode == "auto" { None } else { Some(job.mode.as_str()) };
+            let (mode, size) = detect::detect_layout(in_img, known_mode, job.size)?;
+            println!("using mode={} size={}", mode, size);
+            job.mode = mode;
+            job.size = Some(size);
+        }
+        let size = job.size.unwrap().max(1);
+        
+        let mut columns = HashMap::<String, u32>::new();
+        let mut width = in_img.width().div_ceil(size)*size;
+        let mut height = in_img.height();
+        for filename in &filenames
+        {
+            columns.insert(filename.clone(), width/size);
+            width += sources[filename].width().div_ceil(size)*size;
+            height = height.max(sources[filename].height());
+        }
+        let mut combined = RgbaImage::new(width, height);
+        combined.copy_from(in_img, 0, 0).unwrap();
+        for filename in &filenames
+        {
+            combined.copy_from(&sources[filename], columns[filename]*size, 0).unwrap();
+        }
+        
+        for tile in job.tile_variants.iter_mut().flatten()
+        {
+            tile.tile = if tile.source.is_empty()
+            {
+                (tile.tile.0 + self.offset.0, tile.tile.1 + self.offset.1)
+            }
+            else
+            {
+                (tile.tile.0 + columns[&tile.source], tile.tile.1)
+            };
+            tile.source.clear();
+        }
+        job.origin_tile_list = job.tile_variants.iter().map(|group| group[0].tile).collect();
+        job.offset = (0, 0);
+        Ok((job, combined))
+    }
+    
+    // Assembles every frame (with the tile list already pointing into them) and lays the cells out and scales them as the options say.
+    // Returns the sheets, not padded yet, their alternative cells and the scaled tile size.
+    pub fn build(&self, in_frames : &[RgbaImage]) -> Result<(Vec<RgbaImage>, Vec<VariantCell>, u32), String>
+    {
+        let settings = self.settings(&in_frames[0])?;
+        // every frame gets the same cells, so the variants of the first frame go for all of them
+        let mut sheets = Vec::new();
+        let mut variants = Vec::new();
+        for in_img in in_frames
+        {
+            let (sheet, sheet_variants) = variants::assemble_variants(in_img, &settings, &self.variant_groups());
+            let (sheet, sheet_variants) = variants::rearrange(&sheet, settings.size, &sheet_variants, self.layout);
+            sheets.push(sheet);
+            variants = sheet_variants;
+        }
+        let sheets = sheets.iter().map(|sheet| scale::scale(sheet, settings.size, self.scale, &self.scaler)).collect::<Result<Vec<_>, _>>()?;
+        Ok((sheets, variants, settings.size*self.scale))
+    }
+    
+    // Runs the job on an image in memory instead of its files, with images for the tile list to name by the keys of images.
+    // Returns the output as it would be saved: padded, with the frames side by side.
+    pub fn run_image(&self, input : &RgbaImage, images : &HashMap<String, RgbaImage>) -> Result<RgbaImage, String>
+    {
+        let in_frames = if self.frames > 1 { frames::split(input, self.frames, self.frames_vertical)? } else { vec!(input.clone()) };
+        self.run_frames(&in_frames, images)
+    }
+    
+    // Like run_image(), with the input loaded from a file the way the command line loads it, so that animated GIFs and
+    // Aseprite files give every frame.
+    pub fn run_file(&self, filename : &str, images : &HashMap<String, RgbaImage>) -> Result<RgbaImage, String>
+    {
+        let (in_frames, _) = frames::load(filename, &self.aseprite)?;
+        if in_frames.len() == 1
+        {
+            return self.run_image(&in_frames[0], images);
+        }
+        if self.frames > 1
+        {
+            return Err(format!("{} is already animated, frames= only splits still images", filename));
+        }
+        self.run_frames(&in_frames, images)
+    }
+    
+    fn run_frames(&self, in_frames : &[RgbaImage], images : &HashMap<String, RgbaImage>) -> Result<RgbaImage, String>
+    {
+        let missing = self.tile_sources().into_iter().filter(|name| !images.contains_key(name)).collect::<Vec<_>>();
+        if !missing.is_empty()
+        {
+            return Err(format!("the tile list names images that haven't been given: {}", missing.join(", ")));
+        }
+        let mut combined_frames = Vec::new();
+        let mut combined_job = self.clone();
+        for in_img in in_frames
+        {
+            let (frame_job, combined) = self.with_tile_sources(in_img, images)?;
+            combined_frames.push(combined);
+            combined_job = frame_job;
+        }
+        let (sheets, _, size) = combined_job.build(&combined_frames)?;
+        let padded = sheets.iter().map(|sheet| padding::pad(sheet, size, self.padding, self.margin)).collect::<Vec<_>>();
+        Ok(frames::strip(&padded))
+    }
+    
+    // Parses the command line: <infile> <outfile> <options> [tile list]
+    pub fn from_args(args : &[String]) -> Result<Job, String>
+    {
+        let mut job = Job::default();
+        if let Some(in_filename) = args.first()
+        {
+            job.in_filename = in_filename.clone();
+        }
+        if let Some(out_filename) = args.get(1)
+        {
+            job.out_filename = out_filename.clone();
+        }
+        job.apply_args(args.get(2..).unwrap_or(&[]))?;
+        Ok(job)
+    }
+    
+    // Applies key=value options, --preview FILE and tile coordinates from the command line.
+    pub fn apply_args(&mut self, args : &[String]) -> Result<(), String>
+    {
+        let mut args = args.iter();
+        while let Some(arg) = args.next()
+        {
+            if arg == "--preview"
+            {
+                let filename = args.next().ok_or_else(|| "--preview needs a file name".to_string())?;
+                self.set_option("preview", filename)?;
+                continue;
+            }
+            let parse = arg.splitn(2, '=').collect::<Vec<_>>();
+            if parse.len() == 2
+            {
+                self.set_option(parse[0], parse[1])?;
+                continue;
+            }
+            if arg.contains(',')
+            {
+                self.add_tile(arg)?;
+            }
+        }
+        Ok(())
+    }
+    
+    // Works out everything left to the defaults or to auto detection, printing whatever was guessed.
+    pub fn settings(&self, in_img : &RgbaImage) -> Result<Settings, String>
+    {
+        let mut mode = self.mode.clone();
+        let mut size = self.size.unwrap_or(16);
+        if self.size.is_none() || mode == "auto"
+        {
+            let known_mode = if mode == "auto" { None } else { Some(mode.as_str()) };
+            let (detected_mode, detected_size) = detect::detect_layout(in_img, known_mode, self.size)?;
+            mode = detected_mode;
+            size = detected_size;
+            println!("using mode={} size={}", mode, size);
+        }
+        if size == 0
+        {
+            return Err("size must be at least 1".to_string());
+        }
+        
+        let mut origin_tile_list = self.origin_tile_list.clone();
+        if origin_tile_list.is_empty()
+        {
+            if mode == "basic" || mode == "basic_border"
+            {
+                origin_tile_list.push((0, 0));
+                if in_img.dimensions().1 >= size*2
+                {
+                    origin_tile_list.push((0, 1));
+                }
+                else
+                {
+                    origin_tile_list.push((1, 0));
+                }
+            }
+            else if mode == "3x3" || mode == "4x4"
+            {
+                origin_tile_list.push((0, 0));
+            }
+            else if mode == "3x3plus"
+            {
+                origin_tile_list.push((0, 0));
+                origin_tile_list.push((3, 0));
+            }
+            else if mode == "4x4plus"
+            {
+                origin_tile_list.push((0, 0));
+                origin_tile_list.push((4, 0));
+            }
+            else
+            {
+                origin_tile_list.push((0, 0));
+                origin_tile_list.push((1, 0));
+                origin_tile_list.push((2, 0));
+                origin_tile_list.push((3, 0));
+                origin_tile_list.push((4, 0));
+            }
+        }
+        for r in origin_tile_list.iter_mut()
+        {
+            r.0 += self.offset.0;
+            r.1 += self.offset.1;
+        }
+        
+        let needed = match mode.as_str()
+        {
+            "basic" | "basic_border" | "3x3plus" | "4x4plus" => 2,
+            "3x3" | "4x4" => 1,
+            "minitiles" => 5,
+            _ => return Err(format!("unknown mode {}", mode)),
+        };
+        if origin_tile_list.len() < needed
+        {
+            return Err(format!("mode {} needs {} tiles in the tile list, got {}", mode, needed, origin_tile_list.len()));
+        }
+        
+        let edges = match self.edges.measures(size)
+        {
+            Some(edges) => edges,
+            None =>
+            {
+                // detection reads the tiles, so they have to be in the image
+                assemble::check_tiles(in_img, &mode, size, &origin_tile_list)?;
+                let edges = detect::detect_edges(in_img, &mode, &origin_tile_list, size);
+                println!("using edges={},{},{},{}", edges.0, edges.1, edges.2, edges.3);
+                edges
+            }
+        };
+        
+        let settings = Settings { mode, size, edges, border : self.border, origin_tile_list };
+        assemble::check(in_img, &settings)?;
+        for (i, group) in self.variant_groups().iter().enumerate()
+        {
+            for &(tile, _) in group.iter().skip(1)
+            {
+                let mut variant_settings = settings.clone();
+                variant_settings.origin_tile_list[i] = tile;
+                assemble::check(in_img, &variant_settings)?;
+            }
+        }
+        Ok(settings)
+    }
+    
+    // The cells of the preview map, from map_filename or the built-in map.
+    pub fn preview_map(&self) -> Result<Vec<Vec<bool>>, String>
+    {
+        if self.map_filename.is_empty()
+        {
+            return preview::parse_map(preview::DEFAULT_MAP);
+        }
+        if image::ImageFormat::from_path(&self.map_filename).is_ok()
+        {
+            return Ok(preview::map_from_image(&load_image(&self.map_filename)?));
+        }
+        let text = std::fs::read_to_string(&self.map_filename).map_err(|err| format!("failed to read {}: {}", self.map_filename, err))?;
+        preview::parse_map(&text)
+    }
+}
+
+// A set of jobs to run in one go, optionally packed into a single atlas image.
+pub struct Config
+{
+    pub jobs : Vec<Job>,
+    // empty if there is no atlas
+    pub atlas_filename : String,
+    pub manifest_filename : String,
+}
+
+fn load_image(filename : &str) -> Result<RgbaImage, String>
+{
+    Ok(image::open(filename).map_err(|err| format!("failed to open {}: {}", filename, err))?.to_rgba())
+}
+
+// Every frame of a job's input, from the input image (split up by frames=, or every frame of an animated GIF) and any
+// frame= images, with the frame duration to use. Images from the tile list are put next to each frame, see
+// Job::with_tile_sources(), which gives the job to use with them. Images are only decoded once per run.
+fn load_frames(job : &Job, images : &mut HashMap<String, (Vec<RgbaImage>, Option<u32>)>) -> Result<(Job, Vec<RgbaImage>, u32), String>
+{
+    // the same Aseprite file gives different images with different options
+    let key = |filename : &str| if aseprite::is_aseprite(filename) { format!("{}\n{:?}", filename, job.aseprite) } else { filename.to_string() };
+    let tile_sources = job.tile_sources();
+    for filename in std::iter::once(&job.in_filename).chain(job.frame_filenames.iter()).chain(tile_sources.iter())
+    {
+        if let Entry::Vacant(entry) = images.entry(key(filename))
+        {
+            entry.insert(frames::load(filename, &job.aseprite)?);
+        }
+    }
+    let (first, delay) = &images[&key(&job.in_filename)];
+    let mut in_frames = if job.frames > 1
+    {
+        if first.len() > 1
+        {
+            return Err(format!("{} is already animated, frames= only splits still images", job.in_filename));
+        }
+        frames::split(&first[0], job.frames, job.frames_vertical)?
+    }
+    else
+    {
+        first.clone()
+    };
+    for filename in &job.frame_filenames
+    {
+        in_frames.extend(images[&key(filename)].0.iter().cloned());
+    }
+    if in_frames.iter().any(|frame| frame.dimensions() != in_frames[0].dimensions())
+    {
+        return Err(format!("the frames of {} aren't all the same size", job.in_filename));
+    }
+    
+    // animated tile sources go frame by frame along with the input if they have as many frames, otherwise their first frame is used
+    let count = in_frames.len();
+    let mut resolved = job.clone();
+    let mut combined_job = job.clone();
+    for (i, in_img) in in_frames.iter_mut().enumerate()
+    {
+        let sources = tile_sources.iter().map(|filename|
+        {
+            let source_frames = &images[&key(filename)].0;
+            let frame = if source_frames.len() == count { &source_frames[i] } else { &source_frames[0] };
+            (filename.clone(), frame.clone())
+        }).collect::<HashMap<_, _>>();
+        let (frame_job, combined) = resolved.with_tile_sources(in_img, &sources)?;
+        // only guess the mode and size once
+        resolved.mode = frame_job.mode.clone();
+        resolved.size = frame_job.size;
+        *in_img = combined;
+        combined_job = frame_job;
+    }
+    Ok((combined_job, in_frames, job.frame_duration.or(*delay).unwrap_or(100)))
+}
+
+// Runs every job, decoding each input image only once, then writes the atlas and its manifest if there is one.
+pub fn run_config(config : &Config) -> Result<(), String>
+{
+    let mut images = HashMap::new();
+    let mut outputs = Vec::new();
+    // the palette of every job, so the atlas can keep it if they all have the same one
+    let mut palettes = Vec::new();
+    for job in &config.jobs
+    {
+        let palette = if job.keep_palette { palette::read(&job.in_filename)? } else { None };
+        // the job with its tile list pointing into the combined input, see load_frames()
+        let (job, in_frames, frame_duration) = load_frames(job, &mut images)?;
+        let (sheets, variants, size) = job.build(&in_frames)?;
+        let out_img = &sheets[0];
+        if !variants.is_empty()
+        {
+            let extra = variants::extra_count(job.layout, &variants);
+            println!("{}: {} alternative cells in {} extra rows", job.in_filename, extra, out_img.height()/size - job.layout.rows);
+        }
+        // previews and bitmask overlays are drawn from the sheets as they are, everything else gets spread apart
+        let padded = sheets.iter().map(|sheet| padding::pad(sheet, size, job.padding, job.margin)).collect::<Vec<_>>();
+        if !job.out_filename.is_empty()
+        {
+            frames::save(&padded, frame_duration, palette.as_ref(), &job.out_filename)?;
+        }
+        if !job.preview_filename.is_empty()
+        {
+            let map = job.preview_map()?;
+            let previews = sheets.iter().map(|sheet| preview::render(sheet, size, &map, job.layout, &variants)).collect::<Vec<_>>();
+            frames::save(&previews, frame_duration, None, &job.preview_filename)?;
+        }
+        if !job.bitmask_filename.is_empty()
+        {
+            let bitmask_img = bitmask::draw(out_img, size, &|cell| variants::mask_of(job.layout, &variants, cell));
+            bitmask_img.save(&job.bitmask_filename).map_err(|err| format!("failed to save {}: {}", job.bitmask_filename, err))?;
+        }
+        let name = if !job.name.is_empty() { job.name.clone() } else { file_stem(&job.out_filename).or_else(|| file_stem(&job.in_filename)).unwrap_or_default() };
+        let layout = padding::Layout { columns : out_img.width()/size, rows : out_img.height()/size, size, padding : job.padding, margin : job.margin, frames : sheets.len() as u32, arrangement : job.layout };
+        if !job.cells_dir.is_empty()
+        {
+            let prefix = &name;
+            let files = cells::export(&sheets, &layout, &variants, prefix, &job.cell_names, &job.cells_dir, palette.as_ref())?;
+            let manifest_filename = Path::new(&job.cells_dir).join(format!("{}.json", prefix));
+            std::fs::write(&manifest_filename, cells::manifest(&files, size, sheets.len() as u32)).map_err(|err| format!("failed to save {}: {}", manifest_filename.display(), err))?;
+            println!("{}: saved {} cells to {}", job.in_filename, files.len(), job.cells_dir);
+        }
+        if !job.unity_filename.is_empty()
+        {
+            if job.out_filename.is_empty() || !job.out_filename.to_lowercase().ends_with(".png")
+            {
+                return Err("unity= needs a PNG output to slice into sprites".to_string());
+            }
+            if layout.frames > 1 && variants::extra_count(job.layout, &variants) > 0
+            {
+                println!("{}: unity rules can't be both animated and random, so alternative cells are left out of {}", job.in_filename, job.unity_filename);
+            }
+            // keep the GUID of an existing .meta so that references to the sprites in unity survive regenerating them
+            let meta_filename = format!("{}.meta", job.out_filename);
+            let image_guid = std::fs::read_to_string(&meta_filename).ok()
+                .and_then(|meta| meta.lines().find_map(|line| line.strip_prefix("guid: ").map(|guid| guid.trim().to_string())))
+                .unwrap_or_else(|| unity::guid(&Path::new(&job.out_filename).file_name().unwrap().to_string_lossy()));
+            std::fs::write(&meta_filename, unity::sprite_meta(&name, &image_guid, &layout, &variants)).map_err(|err| format!("failed to save {}: {}", meta_filename, err))?;
+            std::fs::write(&job.unity_filename, unity::rule_tile(&name, &image_guid, &layout, &variants, frame_duration)).map_err(|err| format!("failed to save {}: {}", job.unity_filename, err))?;
+        }
+        if !job.ldtk_filename.is_empty()
+        {
+            if job.out_filename.is_empty() || !job.out_filename.to_lowercase().ends_with(".png")
+            {
+                return Err("ldtk= needs a PNG output for the tileset".to_string());
+            }
+            if job.ldtk_value == 0
+            {
+                return Err("ldtk_value must be at least 1".to_string());
+            }
+            // LDtk wants the image relative to the project, which is taken to be next to the snippet
+            let out_path = Path::new(&job.out_filename);
+            let ldtk_dir = Path::new(&job.ldtk_filename).parent().unwrap_or_else(|| Path::new(""));
+            let image_path = out_path.strip_prefix(ldtk_dir).unwrap_or(out_path).to_string_lossy().to_string();
+            let snippet = ldtk::export(&name, &image_path, &layout, &variants, job.ldtk_value as i32);
+            std::fs::write(&job.ldtk_filename, snippet).map_err(|err| format!("failed to save {}: {}", job.ldtk_filename, err))?;
+        }
+        if !config.atlas_filename.is_empty()
+        {
+            let entry = atlas::AtlasEntry
+            {
+                name : job.name.clone(),
+                tile_size : size,
+                layout : job.layout.name.to_string(),
+                margin : job.margin + job.padding,
+                separation : job.padding*2,
+                variants,
+                frames : sheets.len() as u32,
+                frame_duration,
+                ..atlas::AtlasEntry::default()
+            };
+            outputs.push((frames::strip(&padded), entry));
+        }
+        palettes.push(palette);
+    }
+    
+    if !config.atlas_filename.is_empty()
+    {
+        let (atlas_img, entries) = atlas::pack(outputs);
+        let palette = if palettes.iter().all(|palette| palette.is_some() && *palette == palettes[0]) { palettes[0].as_ref() } else { None };
+        frames::save(&[atlas_img], 0, palette, &config.atlas_filename)?;
+        let image_name = Path::new(&config.atlas_filename).file_name().unwrap().to_string_lossy().to_string();
+        std::fs::write(&config.manifest_filename, atlas::manifest(&image_name, &entries)).map_err(|err| format!("failed to save {}: {}", config.manifest_filename, err))?;
+    }
+    Ok(())
+}
+
+fn toml_to_option(key : &str, value : &toml::Value) -> Result<String, String>
+{
+    match value
+    {
+        toml::Value::String(text) => Ok(text.clone()),
+        toml::Value::Integer(number) => Ok(number.to_string()),
+        toml::Value::Array(list) => Ok(list.iter().map(|value| toml_to_option(key, value)).collect::<Result<Vec<_>, _>>()?.join(",")),
+        _ => Err(format!("unsupported value for {}: {}", key, value)),
+    }
+}
+
+fn file_stem(filename : &str) -> Option<String>
+{
+    Path::new(filename).file_stem().map(|stem| stem.to_string_lossy().to_string())
+}
+
+// Applies the options of one table (the top level or one job) on top of the given job.
+fn apply_table(job : &mut Job, table : &toml::value::Table, base_dir : &Path) -> Result<(), String>
+{
+    for (key, value) in table
+    {
+        match key.as_str()
+        {
+            "job" | "atlas" | "manifest" => continue,
+            "name" => job.name = toml_to_option(key, value)?,
+            "input" => job.in_filename = base_dir.join(toml_to_option(key, value)?).to_string_lossy().to_string(),
+            "frame" =>
+            {
+                job.frame_filenames.clear();
+                for filename in value.as_array().cloned().unwrap_or_else(|| vec!(value.clone()))
+                {
+                    job.frame_filenames.push(base_dir.join(toml_to_option(key, &filename)?).to_string_lossy().to_string());
+                }
+            }
+            "output" => job.out_filename = base_dir.join(toml_to_option(key, value)?).to_string_lossy().to_string(),
+            "preview" => job.preview_filename = base_dir.join(toml_to_option(key, value)?).to_string_lossy().to_string(),
+            "map" => job.map_filename = base_dir.join(toml_to_option(key, value)?).to_string_lossy().to_string(),
+            "bitmask" => job.bitmask_filename = base_dir.join(toml_to_option(key, value)?).to_string_lossy().to_string(),
+            "cells" => job.cells_dir = base_dir.join(toml_to_option(key, value)?).to_string_lossy().to_string(),
+            "unity" => job.unity_filename = base_dir.join(toml_to_option(key, value)?).to_string_lossy().to_string(),
+            "ldtk" => job.ldtk_filename = base_dir.join(toml_to_option(key, value)?).to_string_lossy().to_string(),
+            "tiles" =>
+            {
+                let list = value.as_array().ok_or_else(|| "tiles must be a list".to_string())?;
+                job.origin_tile_list.clear();
+                job.tile_variants.clear();
+                for tile in list
+                {
+                    job.add_tile(&toml_to_option(key, tile)?)?;
+                }
+                for tile in job.tile_variants.iter_mut().flatten().filter(|tile| !tile.source.is_empty())
+                {
+                    tile.source = base_dir.join(&tile.source).to_string_lossy().to_string();
+                }
+            }
+            _ => job.set_option(key, &toml_to_option(key, value)?)?,
+        }
+    }
+    Ok(())
+}
+
+// Turns the command line (without --watch) into jobs, either from a config file or from the options themselves.
+pub fn config_from_args(args : &[String]) -> Result<Config, String>
+{
+    if args.len() == 2 && args[0] == "--config"
+    {
+        load_config(&args[1])
+    }
+    else
+    {
+        Job::from_args(args).map(|job| Config { jobs : vec!(job), atlas_filename : String::new(), manifest_filename : String::new() })
+    }
+}
+
+// Reads the jobs of a config file. Options at the top level are defaults for every [[job]].
+// Paths are relative to the config file.
+pub fn load_config(filename : &str) -> Result<Config, String>
+{
+    let text = std::fs::read_to_string(filename).map_err(|err| format!("failed to read {}: {}", filename, err))?;
+    let config = text.parse::<toml::Value>().map_err(|err| format!("failed to parse {}: {}", filename, err))?;
+    let base_dir = Path::new(filename).parent().unwrap_or_else(|| Path::new(""));
+    let top = config.as_table().unwrap();
+    
+    let mut defaults = Job::default();
+    apply_table(&mut defaults, top, base_dir)?;
+    
+    let path_option = |key : &str| -> Result<String, String>
+    {
+        match top.get(key)
+        {
+            Some(value) => Ok(base_dir.join(toml_to_option(key, value)?).to_string_lossy().to_string()),
+            None => Ok(String::new()),
+        }
+    };
+    let atlas_filename = path_option("atlas")?;
+    let mut manifest_filename = path_option("manifest")?;
+    if !atlas_filename.is_empty() && manifest_filename.is_empty()
+    {
+        manifest_filename = Path::new(&atlas_filename).with_extension("json").to_string_lossy().to_string();
+    }
+    
+    let mut jobs = Vec::new();
+    let list = match top.get("job")
+    {
+        Some(toml::Value::Array(list)) => list.clone(),
+        Some(_) => return Err("job must be an array of tables, use [[job]]".to_string()),
+        None => vec!(toml::Value::Table(toml::value::Table::new())),
+    };
+    for (i, entry) in list.iter().enumerate()
+    {
+        let table = entry.as_table().ok_or_else(|| "job must be an array of tables, use [[job]]".to_string())?;
+        let mut job = defaults.clone();
+        apply_table(&mut job, table, base_dir)?;
+        if job.in_filename.is_empty() || (job.out_filename.is_empty() && job.cells_dir.is_empty() && atlas_filename.is_empty())
+        {
+            return Err(format!("job {} in {} needs an input and an output", i+1, filename));
+        }
+        if job.name.is_empty()
+        {
+            job.name = file_stem(&job.out_filename).unwrap_or_else(|| format!("job {}", i+1));
+        }
+        jobs.push(job);
+    }
+    Ok(Config { jobs, atlas_filename, manifest_filename })
+}
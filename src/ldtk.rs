@@ -0,0 +1,89 @@
+use crate::atlas::json_string;
+use crate::masks::*;
+use crate::padding::Layout;
+use crate::variants;
+use crate::variants::VariantCell;
+
+// uids of everything in the snippet, rules counting up from RULE_UID
+const TILESET_UID : u32 = 9000;
+const LAYER_UID : u32 = 9001;
+const GROUP_UID : u32 = 9002;
+const RULE_UID : u32 = 9100;
+
+// The 3x3 pattern of a rule, top row first: the IntGrid value where a neighbour must be filled, minus it where it must not be,
+// and 0 where it doesn't matter. Corners only matter when both sides next to them are filled, like in godot's 3x3 minimal mode.
+pub fn pattern(mask : u16, value : i32) -> [i32; 9]
+{
+    let state = |bit : u16| if mask & bit != 0 { value } else { -value };
+    let corner = |bit : u16, sides : u16| if mask & sides == sides { state(bit) } else { 0 };
+    [
+        corner(TOP_LEFT, TOP | LEFT), state(TOP), corner(TOP_RIGHT, TOP | RIGHT),
+        state(LEFT), value, state(RIGHT),
+        corner(BOTTOM_LEFT, BOTTOM | LEFT), state(BOTTOM), corner(BOTTOM_RIGHT, BOTTOM | RIGHT),
+    ]
+}
+
+// An LDtk tileset definition for the output image and an IntGrid layer definition whose auto-layer rules draw it,
+// one rule per bitmask, to be pasted into defs.tilesets and defs.layers of a project (the uids might need changing
+// to not clash with the project's own). Cells that share a bitmask are picked from at random, evenly. LDtk has no
+// animated tiles, so only the first frame is used.
+pub fn export(name : &str, image_path : &str, layout : &Layout, variants : &[VariantCell], value : i32) -> String
+{
+    // how LDtk numbers tiles: left to right, top to bottom, over the whole image
+    let per_row = (layout.width() + layout.padding*2 - (layout.margin + layout.padding)*2) / (layout.size + layout.padding*2);
+    let tile_id = |(x, y) : (u32, u32)| y*per_row + x;
+    
+    let mut rules = Vec::<(u16, Vec<u32>)>::new();
+    for y in 0..layout.rows
+    {
+        for x in 0..layout.columns
+        {
+            let mask = variants::mask_of(layout.arrangement, variants, (x, y));
+            if mask == 0
+            {
+                continue;
+            }
+            match rules.iter_mut().find(|rule| rule.0 == mask)
+            {
+                Some(rule) => rule.1.push(tile_id((x, y))),
+                None => rules.push((mask, vec!(tile_id((x, y))))),
+            }
+        }
+    }
+    let rule_list = rules.iter().enumerate().map(|(i, (mask, tiles))|
+    {
+        let ids = tiles.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+        let pattern = pattern(*mask, value).iter().map(|state| state.to_string()).collect::<Vec<_>>();
+        format!(
+"        {{\"uid\": {}, \"active\": true, \"size\": 3, \"tileIds\": [{}], \"tileRectsIds\": [{}], \"alpha\": 1, \"chance\": 1, \"breakOnMatch\": true,
+          \"pattern\": [{}], \"flipX\": false, \"flipY\": false, \"xModulo\": 1, \"yModulo\": 1, \"xOffset\": 0, \"yOffset\": 0,
+          \"tileXOffset\": 0, \"tileYOffset\": 0, \"tileRandomXMin\": 0, \"tileRandomXMax\": 0, \"tileRandomYMin\": 0, \"tileRandomYMax\": 0,
+          \"checker\": \"None\", \"tileMode\": \"Single\", \"pivotX\": 0, \"pivotY\": 0, \"outOfBoundsValue\": null, \"invalidated\": false,
+          \"perlinActive\": false, \"perlinSeed\": 0, \"perlinScale\": 0.2, \"perlinOctaves\": 2}}",
+            RULE_UID + i as u32, ids.join(", "), ids.iter().map(|id| format!("[{}]", id)).collect::<Vec<_>>().join(", "), pattern.join(", "))
+    }).collect::<Vec<_>>();
+    
+    format!(
+"{{
+  \"tileset\": {{
+    \"uid\": {}, \"identifier\": {}, \"relPath\": {}, \"pxWid\": {}, \"pxHei\": {}, \"tileGridSize\": {}, \"spacing\": {}, \"padding\": {},
+    \"tags\": [], \"tagsSourceEnumUid\": null, \"enumTags\": [], \"customData\": [], \"savedSelections\": [], \"cachedPixelData\": null, \"embedAtlas\": null
+  }},
+  \"layer\": {{
+    \"__type\": \"IntGrid\", \"type\": \"IntGrid\", \"identifier\": {}, \"uid\": {}, \"gridSize\": {}, \"displayOpacity\": 1, \"pxOffsetX\": 0, \"pxOffsetY\": 0,
+    \"intGridValues\": [{{\"value\": {}, \"identifier\": {}, \"color\": \"#40A040\", \"tile\": null, \"groupUid\": 0}}], \"intGridValuesGroups\": [],
+    \"autoSourceLayerDefUid\": null, \"tilesetDefUid\": {}, \"autoTilesetDefUid\": {}, \"tilePivotX\": 0, \"tilePivotY\": 0,
+    \"autoRuleGroups\": [
+      {{
+        \"uid\": {}, \"name\": {}, \"active\": true, \"isOptional\": false, \"color\": null, \"icon\": null, \"usesWildcard\": false,
+        \"biomeRequirementMode\": 0, \"requiredBiomeValues\": [],
+        \"rules\": [
+{}
+        ]
+      }}
+    ]
+  }}
+}}
+", TILESET_UID, json_string(name), json_string(image_path), layout.width(), layout.height(), layout.size, layout.padding*2, layout.margin + layout.padding,
+        json_string(name), LAYER_UID, layout.size, value, json_string(name), TILESET_UID, TILESET_UID, GROUP_UID, json_string(name), rule_list.join(",\n"))
+}
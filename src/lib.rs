@@ -0,0 +1,24 @@
+pub mod assemble;
+pub mod aseprite;
+pub mod atlas;
+pub mod bitmask;
+pub mod capi;
+pub mod cells;
+pub mod check;
+pub mod detect;
+pub mod frames;
+pub mod job;
+pub mod ldtk;
+pub mod masks;
+pub mod padding;
+pub mod palette;
+pub mod preview;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod reverse;
+pub mod scale;
+pub mod unity;
+pub mod variants;
+pub mod verify;
+pub mod wasm;
+pub mod watch;
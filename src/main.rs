@@ -1,121 +1,188 @@
 use std::env;
 
-#[allow(unused_mut)]
+use autotyler::check;
+use autotyler::detect;
+use autotyler::frames;
+use autotyler::job;
+use autotyler::masks;
+use autotyler::palette;
+use autotyler::reverse;
+use autotyler::verify;
+use autotyler::watch;
 
-use image::GenericImageView;
-use image::Pixel;
-use core::cmp;
-use std::cell::RefCell;
-
-fn main()
+// autotyler --verify <sheet> [size=N] [edges=...] [layout=NAME]
+fn verify_command(args : &[String]) -> Result<(), String>
 {
-    let mut in_filename = String::new();
-    let mut out_filename = String::new();
-    let mut size = 16;
-    let mut _gap = 0;
-    let mut mode = "basic".to_string();
-    let mut border = 2;
-    // also: minitiles, extended, etc
-    
-    let mut left_edge = 8;
-    let mut right_edge = 8;
-    let mut top_edge = 8;
-    let mut bottom_edge = 8;
-    
-    let mut specified_edges = false;
-    let mut special_edges = false;
-    
-    let mut offset_x = 0;
-    let mut offset_y = 0;
-    
-    let mut origin_tile_list = Vec::<(u32, u32)>::new();
-    
-    for arg in env::args().skip(1)
+    let filename = args.first().ok_or_else(|| "usage: autotyler --verify <sheet> [size=N] [edges=...] [layout=NAME]".to_string())?;
+    let img = image::open(filename).map_err(|err| format!("failed to open {}: {}", filename, err))?.to_rgba();
+    let mut size = None;
+    let mut edges = job::Edges::Default;
+    let mut layout = &masks::GODOT;
+    for arg in &args[1..]
     {
-        if in_filename.len() == 0
+        if let Some(value) = arg.strip_prefix("size=")
         {
-            in_filename = arg.clone();
-            continue;
+            size = Some(value.parse::<u32>().map_err(|_| format!("expected a number, got '{}'", value))?);
         }
-        if out_filename.len() == 0
+        else if let Some(value) = arg.strip_prefix("edges=")
         {
-            out_filename = arg.clone();
-            continue;
+            edges = job::Edges::parse(value)?;
         }
-        
-        let parse = arg.splitn(2, "=").collect::<Vec<_>>();
-        if parse.len() == 2
+        else if let Some(value) = arg.strip_prefix("layout=")
         {
-            match parse[0]
-            {
-                "size" =>
-                {
-                    size = parse[1].parse::<u32>().unwrap();
-                    if !specified_edges
-                    {
-                        left_edge = size/2;
-                        right_edge = size-left_edge;
-                        top_edge = size/2;
-                        bottom_edge = size-top_edge;
-                    }
-                    if special_edges
-                    {
-                        println!("please specify size before edge measures");
-                    }
-                }
-                "edges" =>
-                {
-                    let dims = parse[1].splitn(4, ",").collect::<Vec<_>>();
-                    specified_edges = true;
-                    if dims.len() >= 4
-                    {
-                        left_edge = dims[0].parse::<u32>().unwrap();
-                        top_edge = dims[1].parse::<u32>().unwrap();
-                        right_edge = dims[2].parse::<u32>().unwrap();
-                        bottom_edge = dims[3].parse::<u32>().unwrap();
-                        continue;
-                    }
-                    special_edges = true;
-                    if dims.len() >= 2
-                    {
-                        left_edge = dims[0].parse::<u32>().unwrap();
-                        top_edge = dims[1].parse::<u32>().unwrap_or(left_edge);
-                        right_edge = size-left_edge;
-                        bottom_edge = size-top_edge;
-                    }
-                    else
-                    {
-                        top_edge = dims[0].parse::<u32>().unwrap();
-                        bottom_edge = size-top_edge;
-                    }
-                }
-                "offset" =>
-                {
-                    let dims = parse[1].splitn(2, ",").collect::<Vec<_>>();
-                    offset_x = dims[0].parse::<u32>().unwrap();
-                    offset_y = dims[1].parse::<u32>().unwrap();
-                }
-                "gap" => panic!("gap not implemented yet"),
-                "mode" => mode = parse[1].to_string(),
-                "border" => border = parse[1].parse::<u32>().unwrap(),
-                _ => panic!("unsupported option {}", parse[0]),
-            }
-            continue;
+            layout = masks::arrangement(value).ok_or_else(|| format!("layout must be godot, blob or blob_packed, got '{}'", value))?;
         }
-        let parse = arg.splitn(2, ",").collect::<Vec<_>>();
-        if parse.len() == 2
+        else
+        {
+            return Err(format!("unsupported option {}", arg));
+        }
+    }
+    let size = size.unwrap_or(img.width()/layout.columns);
+    let edges = edges.measures(size).ok_or_else(|| "edges can't be guessed from a finished sheet".to_string())?;
+    let problems = verify::verify(&img, size, edges, layout);
+    for problem in &problems
+    {
+        println!("{}", problem);
+    }
+    if !problems.is_empty()
+    {
+        return Err(format!("{} problems found", problems.len()));
+    }
+    println!("ok");
+    Ok(())
+}
+
+// autotyler --check <infile> <options> [tile list] [overlay=FILE]
+fn check_command(args : &[String]) -> Result<(), String>
+{
+    let in_filename = args.first().ok_or_else(|| "usage: autotyler --check <infile> <options> [tile list] [overlay=FILE]".to_string())?;
+    let mut job = job::Job { in_filename : in_filename.clone(), ..job::Job::default() };
+    let overlay_filename = args.iter().find_map(|arg| arg.strip_prefix("overlay="));
+    job.apply_args(&args[1..].iter().filter(|arg| !arg.starts_with("overlay=")).cloned().collect::<Vec<_>>())?;
+    
+    // only the first frame of animations is checked
+    let in_img = frames::load(&job.in_filename, &job.aseprite)?.0.swap_remove(0);
+    let mut sources = std::collections::HashMap::new();
+    for filename in job.tile_sources()
+    {
+        let source = frames::load(&filename, &job.aseprite)?.0.swap_remove(0);
+        sources.insert(filename, source);
+    }
+    // pixel coordinates are in the input image with the tile list images put to the right of it
+    let (job, in_img) = job.with_tile_sources(&in_img, &sources)?;
+    let settings = job.settings(&in_img)?;
+    let found = check::check(&in_img, &settings)?;
+    for discontinuity in &found
+    {
+        let (a, b) = discontinuity.pixels[0];
+        println!("{} seam in output cell {:?} between tiles {:?} and {:?}: {} pixels don't continue, first at input pixels {:?} and {:?}",
+            if discontinuity.vertical { "vertical" } else { "horizontal" }, discontinuity.cell, discontinuity.tiles.0, discontinuity.tiles.1, discontinuity.pixels.len(), a, b);
+    }
+    if let Some(overlay_filename) = overlay_filename
+    {
+        check::overlay(&in_img, &found).save(overlay_filename).map_err(|err| format!("failed to save {}: {}", overlay_filename, err))?;
+    }
+    if !found.is_empty()
+    {
+        return Err(format!("{} visible seams found", found.len()));
+    }
+    println!("ok");
+    Ok(())
+}
+
+// autotyler --reverse <sheet> <infile> <options> [tile list]
+fn reverse_command(args : &[String]) -> Result<(), String>
+{
+    if args.len() < 2
+    {
+        return Err("usage: autotyler --reverse <sheet> <infile> <options> [tile list]".to_string());
+    }
+    let sheet = image::open(&args[0]).map_err(|err| format!("failed to open {}: {}", args[0], err))?.to_rgba();
+    let mut job = job::Job::from_args(args)?;
+    if args[2..].iter().all(|arg| !arg.starts_with("size="))
+    {
+        job.size = Some(sheet.width()/job.layout.columns);
+    }
+    if !job.tile_sources().is_empty()
+    {
+        return Err("--reverse makes a single input image, so tiles can't name other images".to_string());
+    }
+    if job.mode == "auto" || job.size.is_none() || job.edges == job::Edges::Auto
+    {
+        return Err("mode, size and edges can't be guessed from a finished sheet".to_string());
+    }
+    
+    // an empty input big enough for the mode's tiles, so that settings() can fill in the rest
+    let size = job.size.unwrap();
+    let (columns, rows) = detect::input_shape(&job.mode).ok_or_else(|| format!("unknown mode {}", job.mode))?;
+    let first = job.origin_tile_list.first().copied().unwrap_or((0, 0));
+    let columns = job.origin_tile_list.iter().map(|tile| tile.0 + 1).fold(first.0 + columns, u32::max) + job.offset.0;
+    let rows = job.origin_tile_list.iter().map(|tile| tile.1 + 1).fold(first.1 + rows, u32::max) + job.offset.1;
+    let settings = job.settings(&image::RgbaImage::new(columns*size, rows*size))?;
+    
+    let reversed = reverse::reverse(&sheet, &settings, (columns*size, rows*size), job.layout)?;
+    let palette = if job.keep_palette { palette::read(&args[0])? } else { None };
+    frames::save(&[reversed.input], 0, palette.as_ref(), &job.out_filename)?;
+    for ((x, y), count) in &reversed.unexplained
+    {
+        println!("cell ({}, {}): {} pixels differ", x, y, count);
+    }
+    let total = sheet.width()*sheet.height();
+    println!("{} of {} pixels ({:.1}%) can't be generated by mode={} edges={},{},{},{} and would be lost",
+        reversed.unexplained_total, total, reversed.unexplained_total as f64*100.0/total as f64, settings.mode, settings.edges.0, settings.edges.1, settings.edges.2, settings.edges.3);
+    Ok(())
+}
+
+fn main()
+{
+    let mut args = env::args().skip(1).collect::<Vec<_>>();
+    
+    let command = match args.first().map(|arg| arg.as_str())
+    {
+        Some("--verify") => Some(verify_command as fn(&[String]) -> Result<(), String>),
+        Some("--check") => Some(check_command as fn(&[String]) -> Result<(), String>),
+        Some("--reverse") => Some(reverse_command as fn(&[String]) -> Result<(), String>),
+        _ => None,
+    };
+    if let Some(command) = command
+    {
+        if let Err(err) = command(&args[1..])
         {
-            origin_tile_list.push((parse[0].parse::<u32>().unwrap(), parse[1].parse::<u32>().unwrap()));
+            println!("{}", err);
+            std::process::exit(1);
         }
+        return;
     }
-    if out_filename == ""
+    
+    let watch = args.first().map(|arg| arg == "--watch").unwrap_or(false);
+    if watch
+    {
+        args.remove(0);
+    }
+    
+    let config = match job::config_from_args(&args)
+    {
+        Ok(config) => config,
+        Err(err) =>
+        {
+            println!("{}", err);
+            std::process::exit(1);
+        }
+    };
+    
+    if config.jobs.is_empty() || (config.jobs[0].out_filename.is_empty() && config.atlas_filename.is_empty())
     {
         println!(
 "usage:
 autotyler <infile> <outfile> <options> [tile list]
+autotyler --config <config.toml>
+autotyler --watch <any of the above>
+autotyler --verify <sheet> [size=N] [edges=...] [layout=NAME]
+autotyler --check <infile> <options> [tile list] [overlay=FILE]
+autotyler --reverse <sheet> <infile> <options> [tile list]
 
 options:
-  mode=basic | basic_border | 3x3 | 4x4 | minitiles
+  mode=basic | basic_border | 3x3 | 4x4 | minitiles | auto
     The algorithm used to generate the tilemap.
       basic: 2 tiles, see examples. (default)
       basic_border: 2 tiles, see examples.
@@ -124,14 +191,52 @@ options:
       4x4: 16 tiles, see examples.
       4x4plus: 17 tiles, see examples.
       minitiles: 5 tiles, see https://github.com/lunarfyre7/GodotAutotileAssembler and examples.
-  size=N
+      auto: guesses the mode from the shape of the input image, see size=auto.
+  size=N | auto
     The height and width of the tile in pixels. Currently only support square tiles.
-  edges=LEFT,TOP,RIGHT,BOTTOM | LEFT,TOP | LEFT
+    auto: guesses the tile size from the dimensions of the input image and which of its tiles are empty, and prints the guess. Refuses if more than one mode and size would fit.
+  edges=LEFT,TOP,RIGHT,BOTTOM | LEFT,TOP | LEFT | auto
     The amount of space taken up by edges. Omitted dimensions are generated by subtracting the opposite dimension from the tile size, or by copying the adjacent edge. Only minitiles is guaranteed to work with edges settings where opposite edges don't add up to the tilesize.
+    auto: measures the edges by finding where the edge tiles start matching the fill tile (or become opaque), and prints the result. Edges that can't be measured fall back to half the tile size, and so do all of them if the measured edges would cut through the corners or outlines of the edge tiles (like a thin outline in front of a fill of one flat colour with rounded corners).
   offset=N,N
     The offset, in TILES (not pixels), from the top left corner of the screen from which to search for tiles. Useful for using the same input tilesheet to generate many tilemaps.
   border=N
     Used exclusively by the basic_border algorithm. The default is 2.
+  layout=godot | blob | blob_packed
+    How the cells are laid out in the output.
+      godot: the 12x4 layout of godot's 3x3 minimal autotiles, with the blank cell at 10,1 left empty. (default)
+      blob: 8x6, with tile 0 left empty for engines that draw it as nothing, then the 47 cells sorted by their 8 bit blob bitmask (N=1, NE=2, E=4, SE=8, S=16, SW=32, W=64, NW=128).
+      blob_packed: 8x6, the 47 cells in the same order starting at tile 0. The last cell is left empty.
+    blob and blob_packed are a plain bitmask order, not the autotile template of any particular engine, so check them against what your engine expects.
+    Alternative cells go in extra rows below, as many to a row as the layout is wide. Previews, bitmask overlays, cells=, unity=, ldtk= and the atlas manifest all follow the layout. --verify and --reverse take layout= for sheets in the other layouts.
+  frames=N
+    The input image holds N frames of an animation, side by side (or on top of each other with frame_layout=vertical). Each frame is turned into its own 12x4 sheet. Animated GIF inputs are split into their frames without this.
+  frame=FILE
+    Adds the frames of another image to the animation. Can be given more than once.
+  frame_duration=MS
+    How long each frame is shown, in milliseconds, for GIF outputs and the atlas manifest. Defaults to the frame delay of an animated GIF input, or 100.
+    Animated outputs are saved as the sheets of every frame side by side, or as a looping animated GIF if the output file name ends in .gif. The same goes for previews.
+  layer=NAME, tag=NAME, frame_range=A-B, layers=merged | separate
+    Input images (and frame= and tile list images) can be Aseprite files (.ase or .aseprite). By default every visible layer is drawn and every frame is used, with the frame durations from the file. layer= only uses one layer or group, even a hidden one. tag= only uses the frames of a tag, frame_range= only the given frames (counting from 0). layers=separate puts every layer next to each other instead of drawing them on top of each other, bottom layer first, so each layer can be its own terrain (with offset=) or its own alternatives (in the tile list). Blend modes are all drawn as normal and tilemap layers are left out.
+  --preview FILE (or preview=FILE)
+    Also draws a test map with the generated tiles, picking each tile from its neighbours like godot's 3x3 minimal autotiling does, so seams and corners can be checked without importing the tileset.
+  map=FILE
+    The map to draw the preview with, instead of the built-in one (blobs, one tile wide corridors, tiles touching diagonally and holes). Either a text file where '#' is a filled cell and '.' or a space is empty, or an image where every pixel that isn't transparent or black is a filled cell.
+  bitmask=FILE
+    Also saves a copy of the output with the bitmask of every cell drawn on top as red dots, like godot's editor shows them. Handy for checking the layout or sharing it in reviews.
+  cells=DIR, cell_names=hex | directions
+    Also saves every cell of the output (after scaling) as its own PNG in DIR, named after the output file (or name= in config files) and the cell's bitmask: in hex with cell_names=hex, like grass_0x1ba.png (default), or as the directions of its neighbours with cell_names=directions, like grass_N_E_S_W.png (a cell with no neighbours is grass_none.png). Alternative cells with the same bitmask get _2, _3 and so on. The blank cell is skipped and animated cells are strips of their frames. DIR also gets a JSON manifest (grass.json) listing every file with the cell it came from, its bitmask and its chance of being picked.
+  unity=FILE.asset
+    Also writes a RuleTile asset for unity's 2D Tilemap Extras package, with a rule for every cell that checks its eight neighbours the way godot's 3x3 minimal mode does, plus a .meta file next to the output (out.png.meta) that slices it into tile sized sprites, so both can go straight into a unity project. The output has to be a PNG. Alternative cells become random rules (unity picks between them evenly, ignoring the weights), and animated outputs become animated rules at 1000/frame_duration sprites per second with the tilemap's animation frame rate at 1. An existing .meta keeps its GUID so references to the sprites survive regenerating them.
+  ldtk=FILE.json, ldtk_value=N
+    Also writes an LDtk tileset definition for the output and an IntGrid layer definition with an auto-layer rule for every cell, each looking at the 3x3 neighbourhood like godot's 3x3 minimal mode does, for the \"tileset\" and \"layer\" to be pasted into defs.tilesets and defs.layers of an LDtk project (change the uids if they clash with the project's). The rules look for IntGrid value N (default 1). The output has to be a PNG, and its path is written relative to the folder of FILE. Alternative cells are picked from evenly, and only the first frame of animated outputs is used since LDtk has no animated tiles.
+  scale=N, scaler=nearest | scale2x
+    Scales the output up N times, for games that ship pixel art at 2x or 3x. nearest makes every pixel NxN. (default) scale2x uses the Scale2x (EPX) and Scale3x pixel art scalers, which round off diagonal steps, so N has to be made of 2s and 3s (2, 3, 4, 6, 8, 9...). Each cell is scaled on its own, so cells never bleed into each other. The tile size in the atlas manifest, padding, previews and bitmask overlays are all at the scaled size.
+  padding=N, margin=N
+    Spreads the cells of the output apart for engines that filter or mipmap the tileset: every cell gets its outermost pixels repeated N times on each side (so cells end up 2N pixels apart), and margin=N leaves N empty pixels around the whole output. Animated outputs get this for each frame. Import the tileset with a separation (spacing) of twice the padding and a margin of margin plus padding. Previews and bitmask overlays are drawn without it.
+  palette=rgba | keep
+    rgba: always save outputs as 32-bit RGBA. (default)
+    keep: if the input is an indexed PNG, the output (and the atlas, if every job has the same palette) is saved as an indexed PNG with exactly the same palette, in the same order and at the same bit depth. If the palette has no fully transparent entry, one is added at the end for the empty cells and margins (going up a bit depth if needed), so the existing indices stay the same. If the output ends up with colours that aren't in the palette, or it needs a transparent entry and the palette is already full, it's saved as RGBA with a warning. Previews and bitmask overlays are always RGBA.
 
 Do not place spaces around the = when specifying options.
 
@@ -142,550 +247,54 @@ tile list:
 This gets the first tile from 4,1 and the second tile from 4,3.
 
 The tile list is optional.
+  
+  Tiles can also come from other images by putting the file name in front, like grass.png:0,0 cliffs.png:3,1. Each image is only loaded once. The offset only applies to tiles of the input image.
+  
+  Any entry of the tile list can be a group of alternatives separated by /, each optionally followed by @WEIGHT (default 1), like 0,0/0,3@0.5/other.png:0,4@0.5. Every cell that comes out different with an alternative is added again in extra rows below the 12x4 block, so godot's autotiling picks between cells with the same bitmask at random. With an atlas, the manifest lists these cells with their bitmask and chance of being picked, and the preview mixes them in too.
+
+watch mode:
+  With --watch, autotyler keeps running and regenerates the output whenever the input images, preview maps or the config file change, printing one line per rebuild. Errors, like an input that is still being written, are printed and autotyler waits for the next change. Stop it with ctrl+c.
+
+verifying:
+  --verify checks that a 12x4 sheet (generated or edited by hand) is internally consistent: the blank cell must be fully transparent, and cells that can sit next to the same neighbour must have identical pixels along that seam, apart from the outline of a side that has no neighbour, which the 3x3 and 4x4 modes cut from one picture. edges= gives the edges the sheet was made with and defaults to half the tile. layout=blob or layout=blob_packed checks a sheet in that layout instead. The tile size defaults to the width of the sheet divided by the number of columns of the layout.
+
+checking inputs:
+  --check looks at the input tiles the way the given mode would use them and reports every seam inside an output cell where two input tiles are stitched together without continuing into each other, with the input pixel coordinates on both sides. overlay=FILE also saves a darkened copy of the input with those pixels in red. basic_border draws its own seams, so check its input with mode=basic.
+
+reversing:
+  --reverse turns a finished 12x4 sheet (or a sheet in the layout given by layout=) back into the input tiles of the given mode, so it can be edited in the small format from then on. mode= and edges= must be given (size defaults to the width of the sheet divided by the number of columns of the layout), the offset and tile list say where to put the tiles. Every input pixel gets the colour it has in most of the places it would be copied to. It then lists the cells with pixels that the input can't generate, like hand-painted variations or inner corners in a mode that has none, and how many pixels in total would be lost by regenerating the sheet.
+
+config files:
+  A TOML file with any number of [[job]] tables. Each job takes the same options as the command line, plus input, output and tiles. frame can be a list of file names. Options given at the top level are used by every job that doesn't set them itself. Paths are relative to the config file. The order options are given in doesn't matter.
+  Jobs that share an input image only decode it once, so one tilesheet with many terrains can be turned into many tilemaps by giving each job its own offset.
+  atlas=FILE at the top level also stacks every output into one image, with a JSON manifest (manifest=FILE, or the atlas name with .json) giving the name (name=..., or the output file name), rect, tile size, margin and separation of each tilemap, plus its number of frames and frame duration if it's animated and its alternative cells if it has any. Jobs don't need an output of their own when there is an atlas.
+  Example:
+    mode = \"3x3\"
+    size = 16
+    edges = \"8,6,8,10\"
+    
+    [[job]]
+    input = \"grass.png\"
+    output = \"grass_out.png\"
+    
+    [[job]]
+    input = \"dirt.png\"
+    output = \"dirt_out.png\"
+    mode = \"basic\"
+    tiles = [\"0,0\", \"0,2\"]
+    preview = \"dirt_preview.png\"
 ");
         return;
     }
     
-    let in_img = RefCell::new(image::open(in_filename).unwrap());
-    
-    if origin_tile_list.is_empty()
+    if watch
     {
-        if mode == "basic" || mode == "basic_border"
-        {
-            origin_tile_list.push((0, 0));
-            if in_img.borrow().dimensions().1 >= size*2
-            {
-                origin_tile_list.push((0, 1));
-            }
-            else
-            {
-                origin_tile_list.push((1, 0));
-            }
-        }
-        else if mode == "3x3" || mode == "4x4"
-        {
-            origin_tile_list.push((0, 0));
-        }
-        else if mode == "3x3plus"
-        {
-            origin_tile_list.push((0, 0));
-            origin_tile_list.push((3, 0));
-        }
-        else if mode == "4x4plus"
-        {
-            origin_tile_list.push((0, 0));
-            origin_tile_list.push((4, 0));
-        }
-        else
-        {
-            origin_tile_list.push((0, 0));
-            origin_tile_list.push((1, 0));
-            origin_tile_list.push((2, 0));
-            origin_tile_list.push((3, 0));
-            origin_tile_list.push((4, 0));
-        }
+        watch::watch(&args);
     }
-    for r in origin_tile_list.iter_mut()
-    {
-        r.0 += offset_x;
-        r.1 += offset_y;
-    }
-    
-    let out_img = RefCell::new(image::RgbaImage::new(12*size, 4*size));
-    
-    let copy_tile_ext = |(mut x_in, mut y_in) : (u32, u32), (mut x_out, mut y_out) : (u32, u32), (min_x, min_y, mut max_x, mut max_y) : (u32, u32, u32, u32), column : Option<u32>, row : Option<u32>|
-    {
-        x_in *= size;
-        y_in *= size;
-        x_out *= size;
-        y_out *= size;
-        max_x = size-max_x;
-        max_y = size-max_y;
-        for ix in cmp::max(min_x, 0)..cmp::min(max_x, size)
-        {
-            for iy in cmp::max(min_y, 0)..cmp::min(max_y, size)
-            {
-                out_img.borrow_mut().put_pixel(x_out+ix, y_out+iy, in_img.borrow().get_pixel(x_in+column.unwrap_or(ix), y_in+row.unwrap_or(iy)).to_rgba());
-            }
-        }
-    };
-    let copy_tile_part = |a, b, c| copy_tile_ext(a, b, c, None, None);
-    let copy_tile = |a, b| copy_tile_part(a, b, (0, 0, 0, 0));
-    //let copy_column = |a, b, column| copy_tile_ext(a, b, (0, 0, 0, 0), Some(column), None);
-    //let copy_row = |a, b, row| copy_tile_ext(a, b, (0, 0, 0, 0), None, Some(row));
     
-    let copy_tile_inplace = |(mut x_in, mut y_in) : (u32, u32), (mut x_out, mut y_out) : (u32, u32)|
-    {
-        x_in *= size;
-        y_in *= size;
-        x_out *= size;
-        y_out *= size;
-        for ix in 0..size
-        {
-            for iy in 0..size
-            {
-                let px = out_img.borrow_mut().get_pixel(x_in+ix, y_in+iy).to_rgba();
-                out_img.borrow_mut().put_pixel(x_out+ix, y_out+iy, px);
-            }
-        }
-    };
-    let clear_tile = |(mut x_out, mut y_out) : (u32, u32)|
+    if let Err(err) = job::run_config(&config)
     {
-        x_out *= size;
-        y_out *= size;
-        for ix in 0..size
-        {
-            for iy in 0..size
-            {
-                let px = image::Rgba::from([0, 0, 0, 0]);
-                out_img.borrow_mut().put_pixel(x_out+ix, y_out+iy, px);
-            }
-        }
-    };
-    
-    let copy_4x4_to_12x4 = ||
-    {
-        copy_tile_inplace((2, 1), (5, 1));
-        copy_tile_inplace((2, 1), (5, 2));
-        copy_tile_inplace((2, 1), (6, 1));
-        copy_tile_inplace((2, 1), (6, 2));
-        
-        copy_tile_inplace((2, 1), (9, 1));
-        copy_tile_inplace((2, 1), (9, 2));
-        // blank
-        copy_tile_inplace((2, 1), (10, 2));
-        
-        copy_tile_inplace((2, 1), (9, 0));
-        copy_tile_inplace((2, 1), (11, 1));
-        copy_tile_inplace((2, 1), (8, 2));
-        copy_tile_inplace((2, 1), (10, 3));
-        
-        copy_tile_inplace((2, 1), (4, 0));
-        copy_tile_inplace((2, 1), (7, 0));
-        copy_tile_inplace((2, 1), (4, 3));
-        copy_tile_inplace((2, 1), (7, 3));
-        
-        
-        copy_tile_inplace((1, 0), (8, 0));
-        copy_tile_inplace((1, 2), (8, 3));
-        copy_tile_inplace((3, 0), (11, 0));
-        copy_tile_inplace((3, 2), (11, 3));
-        
-        copy_tile_inplace((2, 0), (5, 0));
-        copy_tile_inplace((2, 0), (6, 0));
-        copy_tile_inplace((2, 0), (10, 0));
-        
-        copy_tile_inplace((2, 2), (5, 3));
-        copy_tile_inplace((2, 2), (6, 3));
-        copy_tile_inplace((2, 2), (9, 3));
-        
-        copy_tile_inplace((1, 1), (4, 1));
-        copy_tile_inplace((1, 1), (4, 2));
-        copy_tile_inplace((1, 1), (8, 1));
-        
-        copy_tile_inplace((3, 1), (7, 1));
-        copy_tile_inplace((3, 1), (7, 2));
-        copy_tile_inplace((3, 1), (11, 2));
-        
-        clear_tile((10, 1));
-    };
-    
-    let m_left = size-right_edge;
-    let m_right = size-left_edge;
-    let m_top = size-bottom_edge;
-    let m_bottom = size-top_edge;
-    
-    let copy_corners = |corner|
-    {
-        copy_tile(corner, (2, 1));
-        
-        copy_tile_part(corner, (1, 0), (m_left, m_top, 0, 0));
-        copy_tile_part(corner, (1, 1), (m_left, 0, 0, 0));
-        copy_tile_part(corner, (1, 2), (m_left, 0, 0, m_bottom));
-        
-        copy_tile_part(corner, (2, 0), (0, m_top, 0, 0));
-        copy_tile_part(corner, (2, 2), (0, 0, 0, m_bottom));
-        
-        copy_tile_part(corner, (3, 0), (0, m_top, m_right, 0));
-        copy_tile_part(corner, (3, 1), (0, 0, m_right, 0));
-        copy_tile_part(corner, (3, 2), (0, 0, m_right, m_bottom));
-        
-        
-        copy_tile_part(corner, (4, 0), (0, m_top, m_right, 0));
-        copy_tile_part(corner, (5, 0), (0, m_top, m_right, 0));
-        copy_tile_part(corner, (5, 2), (0, m_top, m_right, 0));
-        copy_tile_part(corner, (7, 2), (0, m_top, m_right, 0));
-        
-        copy_tile_part(corner, (7, 0), (m_left, m_top, 0, 0));
-        copy_tile_part(corner, (6, 0), (m_left, m_top, 0, 0));
-        copy_tile_part(corner, (6, 2), (m_left, m_top, 0, 0));
-        copy_tile_part(corner, (4, 2), (m_left, m_top, 0, 0));
-        
-        copy_tile_part(corner, (4, 3), (0, 0, m_right, m_bottom));
-        copy_tile_part(corner, (5, 3), (0, 0, m_right, m_bottom));
-        copy_tile_part(corner, (5, 1), (0, 0, m_right, m_bottom));
-        copy_tile_part(corner, (7, 1), (0, 0, m_right, m_bottom));
-        
-        copy_tile_part(corner, (7, 3), (m_left, 0, 0, m_bottom));
-        copy_tile_part(corner, (6, 3), (m_left, 0, 0, m_bottom));
-        copy_tile_part(corner, (6, 1), (m_left, 0, 0, m_bottom));
-        copy_tile_part(corner, (4, 1), (m_left, 0, 0, m_bottom));
-        
-        
-        copy_tile_part(corner, (4, 0), (m_left, 0, 0, 0));
-        copy_tile_part(corner, (4, 3), (m_left, 0, 0, 0));
-        copy_tile_part(corner, (7, 0), (0, 0, m_right, 0));
-        copy_tile_part(corner, (7, 3), (0, 0, m_right, 0));
-        
-        copy_tile_part(corner, (8, 2), (0, 0, m_right, 0));
-        copy_tile_part(corner, (9, 0), (0, 0, 0, m_bottom));
-        copy_tile_part(corner, (10, 3), (0, m_top, 0, 0));
-        copy_tile_part(corner, (11, 1), (m_left, 0, 0, 0));
-        
-        
-        copy_tile_part(corner, (9, 1), (m_left, m_top, 0, 0));
-        copy_tile_part(corner, (9, 1), (0, 0, m_right, m_bottom));
-        
-        copy_tile_part(corner, (10, 2), (m_left, 0, 0, m_bottom));
-        copy_tile_part(corner, (10, 2), (0, m_top, m_right, 0));
-    };
-    
-    let add_coord = |a : (_, _), b : (_, _)| (a.0 + b.0, a.1 + b.1);
-    
-    match mode.as_str()
-    {
-        "basic" =>
-        {
-            let tile_a = origin_tile_list[0];
-            let tile_b = origin_tile_list[1];
-            
-            for ix in 0..12
-            {
-                for iy in 0..4
-                {
-                    copy_tile(tile_a, (ix, iy));
-                }
-            }
-            
-            copy_tile(tile_b, (0, 3));
-            
-            copy_tile_part(tile_b, (0, 0), (0, 0, 0, m_bottom));
-            copy_tile_part(tile_b, (0, 2), (0, m_top, 0, 0));
-            
-            copy_tile_part(tile_b, (1, 3), (0, 0, m_right, 0));
-            copy_tile_part(tile_b, (3, 3), (m_left, 0, 0, 0));
-            
-            copy_tile_part(tile_b, (1, 0), (0, 0, m_right, m_bottom));
-            copy_tile_part(tile_b, (1, 2), (0, m_top, m_right, 0));
-            copy_tile_part(tile_b, (3, 0), (m_left, 0, 0, m_bottom));
-            copy_tile_part(tile_b, (3, 2), (m_left, m_top, 0, 0));
-            
-            copy_4x4_to_12x4();
-        }
-        "basic_border" =>
-        {
-            let tile_a = origin_tile_list[0];
-            let tile_b = origin_tile_list[1];
-            
-            let m_border = size-border;
-            let m_left_border = size-m_right;
-            let m_top_border = size-m_bottom;
-            let m_right_border = size-m_left;
-            let m_bottom_border = size-m_top;
-            
-            for ix in 0..12
-            {
-                for iy in 0..4
-                {
-                    copy_tile(tile_a, (ix, iy));
-                }
-            }
-            
-            copy_tile(tile_b, (0, 3));
-            
-            copy_tile_part(tile_b, (0, 0), (0, 0, 0, m_bottom));
-            copy_tile_part(tile_b, (0, 2), (0, m_top, 0, 0));
-            
-            copy_tile_part(tile_b, (1, 3), (0, 0, m_right, 0));
-            copy_tile_part(tile_b, (3, 3), (m_left, 0, 0, 0));
-            
-            copy_tile_part(tile_b, (1, 0), (0, 0, m_right, m_bottom));
-            copy_tile_part(tile_b, (1, 2), (0, m_top, m_right, 0));
-            copy_tile_part(tile_b, (3, 0), (m_left, 0, 0, m_bottom));
-            copy_tile_part(tile_b, (3, 2), (m_left, m_top, 0, 0));
-            
-            
-            copy_tile_ext(tile_b, (0, 0), (0, m_top_border, m_border, 0), None, Some(m_top));
-            copy_tile_ext(tile_b, (0, 0), (m_border, m_top_border, 0, 0), None, Some(m_top));
-            
-            copy_tile_ext(tile_b, (0, 1), (0, 0, m_border, 0), None, Some(m_top));
-            copy_tile_ext(tile_b, (0, 1), (m_border, 0, 0, 0), None, Some(m_top));
-            
-            copy_tile_ext(tile_b, (0, 2), (0, 0, m_border, m_bottom_border), None, Some(m_top));
-            copy_tile_ext(tile_b, (0, 2), (m_border, 0, 0, m_bottom_border), None, Some(m_top));
-            
-            
-            copy_tile_ext(tile_b, (1, 3), (m_left_border, 0, 0, m_border), Some(m_left), None);
-            copy_tile_ext(tile_b, (1, 3), (m_left_border, m_border, 0, 0), Some(m_left), None);
-            
-            copy_tile_ext(tile_b, (2, 3), (0, 0, 0, m_border), Some(m_left), None);
-            copy_tile_ext(tile_b, (2, 3), (0, m_border, 0, 0), Some(m_left), None);
-            
-            copy_tile_ext(tile_b, (3, 3), (0, 0, m_right_border, m_border), Some(m_left), None);
-            copy_tile_ext(tile_b, (3, 3), (0, m_border, m_right_border, 0), Some(m_left), None);
-            
-            
-            copy_tile_ext(tile_b, (1, 0), (m_left_border, 0, 0, m_border), Some(m_left), None);
-            copy_tile_ext(tile_b, (1, 0), (0, m_top_border, m_border, 0), None, Some(m_top));
-            
-            copy_tile_ext(tile_b, (1, 1), (0, 0, m_border, 0), None, Some(m_top));
-            
-            copy_tile_ext(tile_b, (1, 2), (0, 0, m_border, m_bottom_border), None, Some(m_top));
-            copy_tile_ext(tile_b, (1, 2), (m_left_border, m_border, 0, 0), Some(m_left), None);
-            
-            
-            copy_tile_ext(tile_b, (2, 0), (0, 0, 0, m_border), Some(m_left), None);
-            
-            copy_tile_ext(tile_b, (2, 2), (0, m_border, 0, 0), Some(m_left), None);
-            
-            
-            copy_tile_ext(tile_b, (3, 0), (0, 0, m_right_border, m_border), Some(m_left), None);
-            copy_tile_ext(tile_b, (3, 0), (m_border, m_top_border, 0, 0), None, Some(m_top));
-            
-            copy_tile_ext(tile_b, (3, 1), (m_border, 0, 0, 0), None, Some(m_top));
-            
-            copy_tile_ext(tile_b, (3, 2), (m_border, 0, 0, m_bottom_border), None, Some(m_top));
-            copy_tile_ext(tile_b, (3, 2), (0, m_border, m_right_border, 0), Some(m_left), None);
-            
-            copy_4x4_to_12x4();
-        }
-        "3x3" | "3x3plus" =>
-        {
-            let xm_left = left_edge;
-            let xm_top = top_edge;
-            let xm_right = right_edge;
-            let xm_bottom = bottom_edge;
-            
-            let tile_0_0 = origin_tile_list[0];
-            let tile_0_1 = add_coord(origin_tile_list[0], (0, 1));
-            let tile_0_2 = add_coord(origin_tile_list[0], (0, 2));
-            let tile_1_0 = add_coord(origin_tile_list[0], (1, 0));
-            let tile_1_1 = add_coord(origin_tile_list[0], (1, 1));
-            let tile_1_2 = add_coord(origin_tile_list[0], (1, 2));
-            let tile_2_0 = add_coord(origin_tile_list[0], (2, 0));
-            let tile_2_1 = add_coord(origin_tile_list[0], (2, 1));
-            let tile_2_2 = add_coord(origin_tile_list[0], (2, 2));
-            
-            for ix in 0..size*3
-            {
-                for iy in 0..size*3
-                {
-                    out_img.borrow_mut().put_pixel(ix+size, iy, in_img.borrow().get_pixel(origin_tile_list[0].0 + ix, origin_tile_list[0].1 + iy).to_rgba());
-                }
-            }
-            
-            copy_tile(tile_1_1, (0, 1));
-            copy_tile(tile_1_1, (0, 3));
-            copy_tile(tile_1_1, (2, 3));
-            
-            copy_tile_part(tile_1_0, (0, 3), (0, 0, 0, m_bottom));
-            copy_tile_part(tile_1_2, (0, 3), (0, m_top, 0, 0));
-            copy_tile_part(tile_0_1, (0, 3), (0, 0, m_right, 0));
-            copy_tile_part(tile_2_1, (0, 3), (m_left, 0, 0, 0));
-            
-            copy_tile_part(tile_2_2, (0, 3), (m_left, m_top, 0, 0));
-            copy_tile_part(tile_0_2, (0, 3), (0, m_top, m_right, 0));
-            copy_tile_part(tile_0_0, (0, 3), (0, 0, m_right, m_bottom));
-            copy_tile_part(tile_2_0, (0, 3), (m_left, 0, 0, m_bottom));
-            
-            copy_tile_inplace((0, 3), (0, 0));
-            copy_tile_inplace((0, 3), (0, 2));
-            copy_tile_inplace((0, 3), (1, 3));
-            copy_tile_inplace((0, 3), (3, 3));
-            copy_tile_part(tile_1_1, (0, 0), (0, xm_top, 0, 0));
-            copy_tile_part(tile_1_1, (0, 2), (0, 0, 0, xm_bottom));
-            copy_tile_part(tile_1_1, (1, 3), (xm_left, 0, 0, 0));
-            copy_tile_part(tile_1_1, (3, 3), (0, 0, xm_right, 0));
-            
-            
-            copy_tile_part(tile_2_1, (0, 0), (m_left, xm_top, 0, 0));
-            copy_tile_part(tile_0_1, (0, 0), (0, xm_top, m_right, 0));
-            
-            copy_tile_part(tile_2_1, (0, 1), (m_left, 0, 0, 0));
-            copy_tile_part(tile_0_1, (0, 1), (0, 0, m_right, 0));
-            
-            copy_tile_part(tile_2_1, (0, 2), (m_left, 0, 0, xm_bottom));
-            copy_tile_part(tile_0_1, (0, 2), (0, 0, m_right, xm_bottom));
-            
-            
-            copy_tile_part(tile_1_2, (1, 3), (xm_left, m_top, 0, 0));
-            copy_tile_part(tile_1_0, (1, 3), (xm_left, 0, 0, m_bottom));
-            
-            copy_tile_part(tile_1_2, (2, 3), (0, m_top, 0, 0));
-            copy_tile_part(tile_1_0, (2, 3), (0, 0, 0, m_bottom));
-            
-            copy_tile_part(tile_1_2, (3, 3), (0, m_top, xm_right, 0));
-            copy_tile_part(tile_1_0, (3, 3), (0, 0, xm_right, m_bottom));
-            
-            copy_tile_part(tile_2_2, (3, 3), (0, m_top, 0, 0));
-            copy_tile_part(tile_0_2, (1, 3), (0, m_top, 0, 0));
-            
-            copy_tile_part(tile_2_0, (3, 3), (0, 0, 0, m_bottom));
-            copy_tile_part(tile_0_0, (1, 3), (0, 0, 0, m_bottom));
-            
-            copy_4x4_to_12x4();
-            
-            if mode == "3x3plus"
-            {
-                copy_corners(origin_tile_list[1]);
-            }
-        }
-        "4x4" | "4x4plus" =>
-        {
-            for ix in 0..size*4
-            {
-                for iy in 0..size*4
-                {
-                    out_img.borrow_mut().put_pixel(ix+size, iy, in_img.borrow().get_pixel(origin_tile_list[0].0 + ix, origin_tile_list[0].1 + iy).to_rgba());
-                }
-            }
-            
-            copy_4x4_to_12x4();
-            
-            if mode == "4x4plus"
-            {
-                copy_corners(origin_tile_list[1]);
-            }
-        }
-        "minitiles" =>
-        {
-            let xm_left = left_edge;
-            let xm_top = top_edge;
-            let xm_right = right_edge;
-            let xm_bottom = bottom_edge;
-            
-            let tile_a = origin_tile_list[0];
-            let tile_b = origin_tile_list[1];
-            let tile_c = origin_tile_list[2];
-            let tile_x = origin_tile_list[3];
-            let tile_z = origin_tile_list[4];
-            
-            copy_tile(tile_a, (0, 3));
-            copy_tile(tile_b, (0, 1));
-            copy_tile(tile_c, (2, 3));
-            copy_tile(tile_x, (2, 1));
-            copy_tile(tile_z, (9, 2));
-            
-            copy_tile(tile_b, (0, 0));
-            copy_tile_part(tile_a, (0, 0), (0, 0, 0, m_bottom));
-            copy_tile(tile_b, (0, 2));
-            copy_tile_part(tile_a, (0, 2), (0, m_top, 0, 0));
-            
-            copy_tile(tile_c, (1, 3));
-            copy_tile_part(tile_a, (1, 3), (0, 0, m_right, 0));
-            copy_tile(tile_c, (3, 3));
-            copy_tile_part(tile_a, (3, 3), (m_left, 0, 0, 0));
-            
-            
-            copy_tile(tile_a, (1, 0));
-            copy_tile_part(tile_b, (1, 0), (0, m_top, 0, 0));
-            copy_tile_part(tile_c, (1, 0), (m_left, 0, 0, 0));
-            copy_tile_part(tile_x, (1, 0), (m_left, m_top, 0, 0));
-            
-            copy_tile(tile_c, (2, 0));
-            copy_tile_part(tile_x, (2, 0), (0, m_top, 0, 0));
-            
-            copy_tile(tile_a, (3, 0));
-            copy_tile_part(tile_b, (3, 0), (0, m_top, 0, 0));
-            copy_tile_part(tile_c, (3, 0), (0, 0, m_right, 0));
-            copy_tile_part(tile_x, (3, 0), (0, m_top, m_right, 0));
-            
-            
-            copy_tile(tile_b, (1, 1));
-            copy_tile_part(tile_x, (1, 1), (m_left, 0, 0, 0));
-            
-            copy_tile(tile_b, (3, 1));
-            copy_tile_part(tile_x, (3, 1), (0, 0, m_right, 0));
-            
-            
-            
-            copy_tile(tile_a, (1, 2));
-            copy_tile_part(tile_b, (1, 2), (0, 0, 0, m_bottom));
-            copy_tile_part(tile_c, (1, 2), (m_left, 0, 0, 0));
-            copy_tile_part(tile_x, (1, 2), (m_left, 0, 0, m_bottom));
-            
-            copy_tile(tile_c, (2, 2));
-            copy_tile_part(tile_x, (2, 2), (0, 0, 0, m_bottom));
-            
-            copy_tile(tile_a, (3, 2));
-            copy_tile_part(tile_b, (3, 2), (0, 0, 0, m_bottom));
-            copy_tile_part(tile_c, (3, 2), (0, 0, m_right, 0));
-            copy_tile_part(tile_x, (3, 2), (0, 0, m_right, m_bottom));
-            
-            
-            copy_4x4_to_12x4();
-            
-            
-            copy_tile(tile_x, (4, 0));
-            copy_tile(tile_x, (7, 0));
-            copy_tile(tile_x, (4, 3));
-            copy_tile(tile_x, (7, 3));
-            
-            copy_tile(tile_z, (5, 1));
-            copy_tile(tile_z, (6, 1));
-            copy_tile(tile_z, (5, 2));
-            copy_tile(tile_z, (6, 2));
-            
-            copy_tile_part(tile_z, (4, 0), (0, 0, xm_right, xm_bottom));
-            copy_tile_part(tile_z, (7, 0), (xm_left, 0, 0, xm_bottom));
-            copy_tile_part(tile_z, (4, 3), (0, xm_top, xm_right, 0));
-            copy_tile_part(tile_z, (7, 3), (xm_left, xm_top, 0, 0));
-            
-            copy_tile_part(tile_x, (5, 1), (0, 0, m_right, m_bottom));
-            copy_tile_part(tile_x, (6, 1), (m_left, 0, 0, m_bottom));
-            copy_tile_part(tile_x, (5, 2), (0, m_top, m_right, 0));
-            copy_tile_part(tile_x, (6, 2), (m_left, m_top, 0, 0));
-            
-            copy_tile_part(tile_z, (5, 0), (xm_left, xm_top, 0, 0));
-            copy_tile_part(tile_z, (6, 0), (0, xm_top, xm_right, 0));
-            copy_tile_part(tile_z, (5, 3), (xm_left, 0, 0, xm_bottom));
-            copy_tile_part(tile_z, (6, 3), (0, 0, xm_right, xm_bottom));
-            copy_tile_part(tile_z, (4, 1), (xm_left, xm_top, 0, 0));
-            copy_tile_part(tile_z, (7, 1), (0, xm_top, xm_right, 0));
-            copy_tile_part(tile_z, (4, 2), (xm_left, 0, 0, xm_bottom));
-            copy_tile_part(tile_z, (7, 2), (0, 0, xm_right, xm_bottom));
-            
-            
-            
-            copy_tile_part(tile_z, (8, 0), (xm_left, xm_top, 0, 0));
-            copy_tile_part(tile_z, (9, 0), (0, xm_top, 0, 0));
-            copy_tile_part(tile_z, (10, 0), (0, xm_top, 0, 0));
-            copy_tile_part(tile_z, (11, 0), (0, xm_top, xm_right, 0));
-            
-            copy_tile_part(tile_z, (8, 3), (xm_left, 0, 0, xm_bottom));
-            copy_tile_part(tile_z, (9, 3), (0, 0, 0, xm_bottom));
-            copy_tile_part(tile_z, (10, 3), (0, 0, 0, xm_bottom));
-            copy_tile_part(tile_z, (11, 3), (0, 0, xm_right, xm_bottom));
-            
-            
-            copy_tile_part(tile_z, (8, 1), (xm_left, 0, 0, 0));
-            copy_tile_part(tile_z, (8, 2), (xm_left, 0, 0, 0));
-            
-            copy_tile_part(tile_z, (11, 1), (0, 0, xm_right, 0));
-            copy_tile_part(tile_z, (11, 2), (0, 0, xm_right, 0));
-            
-            copy_tile_part(tile_z, (9, 1), (xm_left, 0, 0, xm_bottom));
-            copy_tile_part(tile_z, (9, 1), (0, xm_top, xm_right, 0));
-            
-            copy_tile_part(tile_z, (10, 2), (0, 0, xm_right, xm_bottom));
-            copy_tile_part(tile_z, (10, 2), (xm_left, xm_top, 0, 0));
-            
-            
-            copy_tile(tile_z, (9, 2));
-            clear_tile((10, 1));
-        }
-        _ => panic!("unknown mode {}", mode)
+        println!("{}", err);
+        std::process::exit(1);
     }
-    
-    out_img.into_inner().save(out_filename).unwrap();
 }
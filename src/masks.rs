@@ -0,0 +1,123 @@
+// Bits of a godot 3x3 minimal autotile bitmask.
+pub const TOP_LEFT : u16 = 1;
+pub const TOP : u16 = 2;
+pub const TOP_RIGHT : u16 = 4;
+pub const LEFT : u16 = 8;
+pub const CENTER : u16 = 16;
+pub const RIGHT : u16 = 32;
+pub const BOTTOM_LEFT : u16 = 64;
+pub const BOTTOM : u16 = 128;
+pub const BOTTOM_RIGHT : u16 = 256;
+
+// The bitmask of every cell of the 12x4 output, indexed by [y][x]. The blank cell is 0.
+pub const GODOT_12X4 : [[u16; 12]; 4] = [
+    [0x090, 0x0b0, 0x0b8, 0x098, 0x0bb, 0x1b8, 0x0f8, 0x0be, 0x1b0, 0x1fa, 0x1f8, 0x0d8],
+    [0x092, 0x0b2, 0x0ba, 0x09a, 0x1b2, 0x1fe, 0x1fb, 0x0da, 0x1b6, 0x0fe, 0x000, 0x0fb],
+    [0x012, 0x032, 0x03a, 0x01a, 0x0b6, 0x1bf, 0x0ff, 0x09b, 0x1be, 0x1ff, 0x1bb, 0x0db],
+    [0x010, 0x030, 0x038, 0x018, 0x0fa, 0x03e, 0x03b, 0x1ba, 0x036, 0x03f, 0x0bf, 0x01b],
+];
+
+pub fn godot_mask((x, y) : (u32, u32)) -> u16
+{
+    GODOT_12X4[y as usize][x as usize]
+}
+
+// The 3x3 minimal bitmask of a filled cell, given which of its eight neighbours are filled as [y][x] (the middle is ignored).
+// Corners only count when both sides next to them are filled, like godot does.
+pub fn minimal_mask(neighbours : [[bool; 3]; 3]) -> u16
+{
+    let mut mask = CENTER;
+    let sides = [(TOP, 1, 0), (LEFT, 0, 1), (RIGHT, 2, 1), (BOTTOM, 1, 2)];
+    for &(bit, x, y) in &sides
+    {
+        if neighbours[y][x]
+        {
+            mask |= bit;
+        }
+    }
+    let corners = [(TOP_LEFT, TOP | LEFT, 0, 0), (TOP_RIGHT, TOP | RIGHT, 2, 0), (BOTTOM_LEFT, BOTTOM | LEFT, 0, 2), (BOTTOM_RIGHT, BOTTOM | RIGHT, 2, 2)];
+    for &(bit, needs, x, y) in &corners
+    {
+        if neighbours[y][x] && mask & needs == needs
+        {
+            mask |= bit;
+        }
+    }
+    mask
+}
+
+// The cell of the 12x4 output that has the given bitmask.
+pub fn godot_cell(mask : u16) -> Option<(u32, u32)>
+{
+    (0..4).flat_map(|y| (0..12).map(move |x| (x, y))).find(|&cell| mask != 0 && godot_mask(cell) == mask)
+}
+
+// A way of laying the 47 cells out in a sheet: the bitmask of every cell by [y][x], 0 for cells that are left empty.
+pub struct Arrangement
+{
+    pub name : &'static str,
+    pub columns : u32,
+    pub rows : u32,
+    pub masks : &'static [&'static [u16]],
+}
+
+impl Arrangement
+{
+    pub fn mask(&self, (x, y) : (u32, u32)) -> u16
+    {
+        self.masks.get(y as usize).and_then(|row| row.get(x as usize)).copied().unwrap_or(0)
+    }
+    
+    // the cell with the given bitmask
+    pub fn cell(&self, mask : u16) -> Option<(u32, u32)>
+    {
+        let columns = self.columns;
+        (0..self.rows).flat_map(|y| (0..columns).map(move |x| (x, y))).find(|&cell| mask != 0 && self.mask(cell) == mask)
+    }
+}
+
+pub const GODOT : Arrangement = Arrangement
+{
+    name : "godot",
+    columns : 12,
+    rows : 4,
+    masks : &[&GODOT_12X4[0], &GODOT_12X4[1], &GODOT_12X4[2], &GODOT_12X4[3]],
+};
+
+// The 47 cells 8 to a row, sorted by their 8 bit blob bitmask (N=1, NE=2, E=4, SE=8, S=16, SW=32, W=64, NW=128),
+// after an empty cell for engines that draw tile 0 as nothing. This is not any engine's own template.
+pub const BLOB : Arrangement = Arrangement
+{
+    name : "blob",
+    columns : 8,
+    rows : 6,
+    masks : &[
+        &[0x000, 0x010, 0x012, 0x030, 0x032, 0x036, 0x090, 0x092],
+        &[0x0b0, 0x0b2, 0x0b6, 0x1b0, 0x1b2, 0x1b6, 0x018, 0x01a],
+        &[0x038, 0x03a, 0x03e, 0x098, 0x09a, 0x0b8, 0x0ba, 0x0be],
+        &[0x1b8, 0x1ba, 0x1be, 0x0d8, 0x0da, 0x0f8, 0x0fa, 0x0fe],
+        &[0x1f8, 0x1fa, 0x1fe, 0x01b, 0x03b, 0x03f, 0x09b, 0x0bb],
+        &[0x0bf, 0x1bb, 0x1bf, 0x0db, 0x0fb, 0x0ff, 0x1fb, 0x1ff],
+    ],
+};
+
+// The same order starting at tile 0, for engines that mark empty tiles some other way. The last cell is left empty.
+pub const BLOB_PACKED : Arrangement = Arrangement
+{
+    name : "blob_packed",
+    columns : 8,
+    rows : 6,
+    masks : &[
+        &[0x010, 0x012, 0x030, 0x032, 0x036, 0x090, 0x092, 0x0b0],
+        &[0x0b2, 0x0b6, 0x1b0, 0x1b2, 0x1b6, 0x018, 0x01a, 0x038],
+        &[0x03a, 0x03e, 0x098, 0x09a, 0x0b8, 0x0ba, 0x0be, 0x1b8],
+        &[0x1ba, 0x1be, 0x0d8, 0x0da, 0x0f8, 0x0fa, 0x0fe, 0x1f8],
+        &[0x1fa, 0x1fe, 0x01b, 0x03b, 0x03f, 0x09b, 0x0bb, 0x0bf],
+        &[0x1bb, 0x1bf, 0x0db, 0x0fb, 0x0ff, 0x1fb, 0x1ff, 0x000],
+    ],
+};
+
+pub fn arrangement(name : &str) -> Option<&'static Arrangement>
+{
+    [&GODOT, &BLOB, &BLOB_PACKED].iter().find(|arrangement| arrangement.name == name).copied()
+}
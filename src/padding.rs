@@ -0,0 +1,81 @@
+use image::RgbaImage;
+
+use crate::masks::Arrangement;
+
+// Spreads the cells of a sheet apart so that filtering doesn't bleed neighbouring tiles into each other.
+// Every cell gets its outermost pixels repeated `padding` times on each side, so cells end up 2*padding apart,
+// and the whole sheet gets a transparent margin of `margin` pixels around it.
+pub fn pad(sheet : &RgbaImage, size : u32, padding : u32, margin : u32) -> RgbaImage
+{
+    if padding == 0 && margin == 0
+    {
+        return sheet.clone();
+    }
+    let (columns, rows) = (sheet.width()/size, sheet.height()/size);
+    let stride = size + padding*2;
+    let mut img = RgbaImage::new(columns*stride + margin*2, rows*stride + margin*2);
+    for cy in 0..rows
+    {
+        for cx in 0..columns
+        {
+            for y in 0..stride
+            {
+                for x in 0..stride
+                {
+                    // the nearest pixel of the cell, which is the pixel itself inside the cell
+                    let sx = (x as i64 - padding as i64).clamp(0, size as i64 - 1) as u32;
+                    let sy = (y as i64 - padding as i64).clamp(0, size as i64 - 1) as u32;
+                    let px = *sheet.get_pixel(cx*size + sx, cy*size + sy);
+                    img.put_pixel(margin + cx*stride + x, margin + cy*stride + y, px);
+                }
+            }
+        }
+    }
+    img
+}
+
+// Where a cell's pixels start in a padded sheet.
+pub fn cell_origin((cx, cy) : (u32, u32), size : u32, padding : u32, margin : u32) -> (u32, u32)
+{
+    let stride = size + padding*2;
+    (margin + padding + cx*stride, margin + padding + cy*stride)
+}
+
+// Where the cells of an output are once it's saved: padded, with the frames side by side.
+#[derive(Clone, Copy)]
+pub struct Layout
+{
+    pub columns : u32,
+    pub rows : u32,
+    pub size : u32,
+    pub padding : u32,
+    pub margin : u32,
+    pub frames : u32,
+    // which cell is which
+    pub arrangement : &'static Arrangement,
+}
+
+impl Layout
+{
+    pub fn frame_width(&self) -> u32
+    {
+        self.columns*(self.size + self.padding*2) + self.margin*2
+    }
+    
+    pub fn width(&self) -> u32
+    {
+        self.frame_width()*self.frames
+    }
+    
+    pub fn height(&self) -> u32
+    {
+        self.rows*(self.size + self.padding*2) + self.margin*2
+    }
+    
+    // the top left pixel of a cell in the given frame
+    pub fn cell_origin(&self, cell : (u32, u32), frame : u32) -> (u32, u32)
+    {
+        let (x, y) = cell_origin(cell, self.size, self.padding, self.margin);
+        (frame*self.frame_width() + x, y)
+    }
+}
@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufWriter;
+
+use image::RgbaImage;
+
+// The palette of an indexed PNG, with the alpha of each entry.
+#[derive(Clone, PartialEq)]
+pub struct Palette
+{
+    pub colours : Vec<[u8; 4]>,
+    // bits per pixel
+    pub depth : u8,
+}
+
+// Reads the palette of an indexed PNG. Returns None for anything else.
+pub fn read(filename : &str) -> Result<Option<Palette>, String>
+{
+    if !filename.to_lowercase().ends_with(".png")
+    {
+        return Ok(None);
+    }
+    let file = File::open(filename).map_err(|err| format!("failed to open {}: {}", filename, err))?;
+    let (_, reader) = png::Decoder::new(file).read_info().map_err(|err| format!("failed to open {}: {}", filename, err))?;
+    let info = reader.info();
+    if info.color_type != png::ColorType::Indexed
+    {
+        return Ok(None);
+    }
+    let rgb = info.palette.clone().unwrap_or_default();
+    let trns = info.trns.clone().unwrap_or_default();
+    let colours = rgb.chunks(3).enumerate().map(|(i, c)| [c[0], c[1], c[2], *trns.get(i).unwrap_or(&255)]).collect();
+    Ok(Some(Palette { colours, depth : info.bit_depth as u8 }))
+}
+
+// The palette index of every pixel, or None if the image has colours that aren't in the palette.
+// Colours that are in the palette more than once get the first index. Every fully transparent pixel
+// gets the first fully transparent entry, whatever its colour.
+pub fn index(img : &RgbaImage, palette : &Palette) -> Option<Vec<u8>>
+{
+    let mut lookup = HashMap::<[u8; 4], u8>::new();
+    for (i, colour) in palette.colours.iter().enumerate().take(256).rev()
+    {
+        lookup.insert(*colour, i as u8);
+    }
+    let transparent = palette.colours.iter().position(|colour| colour[3] == 0).map(|i| i as u8);
+    img.pixels().map(|px| if px[3] == 0 { transparent } else { lookup.get(&px.0).copied() }).collect()
+}
+
+// The palette with a fully transparent entry added at the end if the image needs one and the palette has none,
+// like the empty cells and margins of an output made from opaque tiles. The bit depth goes up if the entry doesn't fit.
+pub fn with_transparent(img : &RgbaImage, palette : &Palette) -> Palette
+{
+    let mut palette = palette.clone();
+    if palette.colours.len() < 256 && !palette.colours.iter().any(|colour| colour[3] == 0) && img.pixels().any(|px| px[3] == 0)
+    {
+        palette.colours.push([0, 0, 0, 0]);
+        while (1usize << palette.depth) < palette.colours.len()
+        {
+            palette.depth *= 2;
+        }
+    }
+    palette
+}
+
+// Saves an image as an indexed PNG with the given palette, at the palette's bit depth, with a transparent entry added
+// if needed (see with_transparent()). Returns false without saving anything if the image has colours that aren't in the palette.
+pub fn save(img : &RgbaImage, palette : &Palette, filename : &str) -> Result<bool, String>
+{
+    let palette = &with_transparent(img, palette);
+    let indices = match index(img, palette)
+    {
+        Some(indices) => indices,
+        None => return Ok(false),
+    };
+    let depth = palette.depth as usize;
+    let per_byte = 8/depth;
+    let row_bytes = (img.width() as usize).div_ceil(per_byte);
+    let mut data = vec!(0u8; row_bytes*img.height() as usize);
+    for (i, index) in indices.iter().enumerate()
+    {
+        let (x, y) = (i % img.width() as usize, i / img.width() as usize);
+        // the leftmost pixel goes in the highest bits
+        let shift = 8 - depth - (x % per_byte)*depth;
+        data[y*row_bytes + x/per_byte] |= index << shift;
+    }
+    
+    let fail = |err : png::EncodingError| format!("failed to save {}: {}", filename, err);
+    let file = File::create(filename).map_err(|err| format!("failed to save {}: {}", filename, err))?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), img.width(), img.height());
+    encoder.set_color(png::ColorType::Indexed);
+    encoder.set_depth(png::BitDepth::from_u8(palette.depth).unwrap_or(png::BitDepth::Eight));
+    encoder.set_palette(palette.colours.iter().flat_map(|colour| colour[..3].to_vec()).collect());
+    let alphas = palette.colours.iter().map(|colour| colour[3]).collect::<Vec<_>>();
+    // only up to the last entry that isn't opaque
+    if let Some(last) = alphas.iter().rposition(|&alpha| alpha != 255)
+    {
+        encoder.set_trns(alphas[..=last].to_vec());
+    }
+    encoder.write_header().map_err(fail)?.write_image_data(&data).map_err(fail)?;
+    Ok(true)
+}
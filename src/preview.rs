@@ -0,0 +1,103 @@
+use image::Rgba;
+use image::RgbaImage;
+
+use crate::masks;
+use crate::variants::VariantCell;
+
+// Blobs, one tile wide corridors, tiles that only touch diagonally, and holes.
+pub const DEFAULT_MAP : &str = "\
+..................................
+.####....#########...#.#.#....#...
+.####....#.......#....#.#.....#...
+.####....#.#####.#...#.#.#..#####.
+.........#.#...#.#....#.#.....#...
+.######..#.#.#.#.#...#.#.#....#...
+.##..##..#.#...#.#................
+.##..##..#.#####.#...##...##..###.
+.######..#.......#...##..###..#.#.
+.........#########.....##.....###.
+.###.###...............##.........
+.#######..#######..#.........#....
+.###.###..##.#.##..###...#####....
+..........#######..#####..##......
+..................................";
+
+// Parses a map where '#' (or anything but '.' and spaces) is a filled cell. Lines don't need to be the same length.
+pub fn parse_map(text : &str) -> Result<Vec<Vec<bool>>, String>
+{
+    let map = text.lines().map(|line| line.trim_end().chars().map(|c| c != '.' && c != ' ').collect::<Vec<_>>()).collect::<Vec<_>>();
+    if map.iter().all(|row| row.is_empty())
+    {
+        return Err("the preview map is empty".to_string());
+    }
+    Ok(map)
+}
+
+// A bitmap map: every pixel that isn't fully transparent or black is a filled cell.
+pub fn map_from_image(img : &RgbaImage) -> Vec<Vec<bool>>
+{
+    (0..img.height()).map(|y| (0..img.width()).map(|x|
+    {
+        let px = img.get_pixel(x, y);
+        px[3] != 0 && (px[0] != 0 || px[1] != 0 || px[2] != 0)
+    }).collect()).collect()
+}
+
+// Picks one of the cells with the given bitmask by their chances, the same way every time for the same map position.
+fn pick_variant(variants : &[VariantCell], mask : u16, (x, y) : (i64, i64)) -> Option<(u32, u32)>
+{
+    let mut hash = (x as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15) ^ (y as u64).wrapping_mul(0xc2b2_ae3d_27d4_eb4f);
+    hash ^= hash >> 29;
+    hash = hash.wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    hash ^= hash >> 32;
+    let mut roll = (hash >> 11) as f64 / (1u64 << 53) as f64;
+    for variant in variants.iter().filter(|variant| variant.mask == mask)
+    {
+        if roll < variant.probability
+        {
+            return Some(variant.cell);
+        }
+        roll -= variant.probability;
+    }
+    None
+}
+
+// Draws the map with the tiles of a generated sheet, picking each tile from its neighbours the way godot does.
+// Cells with alternatives are picked at random by their chances.
+pub fn render(sheet : &RgbaImage, size : u32, map : &[Vec<bool>], arrangement : &masks::Arrangement, variants : &[VariantCell]) -> RgbaImage
+{
+    let filled = |x : i64, y : i64| x >= 0 && y >= 0 && map.get(y as usize).and_then(|row| row.get(x as usize)).copied().unwrap_or(false);
+    let width = map.iter().map(|row| row.len()).max().unwrap_or(0) as u32;
+    let height = map.len() as u32;
+    
+    let mut img = RgbaImage::from_pixel(width*size, height*size, Rgba([0, 0, 0, 0]));
+    for y in 0..height as i64
+    {
+        for x in 0..width as i64
+        {
+            if !filled(x, y)
+            {
+                continue;
+            }
+            let mut neighbours = [[false; 3]; 3];
+            for (ny, row) in neighbours.iter_mut().enumerate()
+            {
+                for (nx, neighbour) in row.iter_mut().enumerate()
+                {
+                    *neighbour = filled(x + nx as i64 - 1, y + ny as i64 - 1);
+                }
+            }
+            // every minimal mask is somewhere in the sheet
+            let mask = masks::minimal_mask(neighbours);
+            let (tx, ty) = pick_variant(variants, mask, (x, y)).unwrap_or_else(|| arrangement.cell(mask).unwrap());
+            for iy in 0..size
+            {
+                for ix in 0..size
+                {
+                    img.put_pixel(x as u32*size + ix, y as u32*size + iy, *sheet.get_pixel(tx*size + ix, ty*size + iy));
+                }
+            }
+        }
+    }
+    img
+}
@@ -0,0 +1,119 @@
+// Python bindings, built with the python feature (pip install . or maturin build, see pyproject.toml).
+//
+//     import autotyler
+//     pixels, width, height = autotyler.assemble("grass.png", mode="3x3", edges=(8, 6, 8, 10))
+//     pixels, width, height = autotyler.assemble(array, mode="minitiles", tiles=["0,0", "1,0", "2,0", "3,0", "4,0"])
+//
+// The input is a file name, loaded like on the command line (with every frame of animated GIFs and Aseprite files), or
+// RGBA pixels as anything with the buffer protocol (bytes, bytearray, a uint8 numpy array of shape (height, width, 4)),
+// with width and height given for flat buffers. Options are the command line options as keyword arguments. The output
+// is the bytes of the RGBA pixels with their width and height, e.g. for numpy.frombuffer().
+// #[pyfunction] expands to conversions that clippy doesn't like.
+#![allow(clippy::useless_conversion)]
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+use image::RgbaImage;
+use pyo3::buffer::PyBuffer;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use pyo3::types::PyDict;
+use pyo3::types::PyList;
+use pyo3::types::PyTuple;
+
+use crate::frames;
+use crate::job::Job;
+
+fn error(err : String) -> PyErr
+{
+    PyValueError::new_err(err)
+}
+
+// An option as the command line would have it: numbers as they are and lists joined with commas, like edges=(8, 6, 8, 10).
+fn option_text(value : &Bound<PyAny>) -> PyResult<String>
+{
+    if value.is_instance_of::<PyList>() || value.is_instance_of::<PyTuple>()
+    {
+        let parts = value.iter()?.map(|part| option_text(&part?)).collect::<PyResult<Vec<_>>>()?;
+        return Ok(parts.join(","));
+    }
+    Ok(value.str()?.to_string())
+}
+
+// The first frame of an image file, loaded like on the command line.
+fn load(filename : &str, job : &Job) -> PyResult<RgbaImage>
+{
+    let (mut frames, _) = frames::load(filename, &job.aseprite).map_err(error)?;
+    Ok(frames.swap_remove(0))
+}
+
+fn image(value : &Bound<PyAny>, width : Option<u32>, height : Option<u32>, job : &Job) -> PyResult<RgbaImage>
+{
+    if let Ok(filename) = value.extract::<String>()
+    {
+        return load(&filename, job);
+    }
+    let buffer = PyBuffer::<u8>::get_bound(value)?;
+    let shape = buffer.shape();
+    let (width, height) = match (width, height)
+    {
+        (Some(width), Some(height)) => (width, height),
+        _ if shape.len() == 3 && shape[2] == 4 => (shape[1] as u32, shape[0] as u32),
+        _ => return Err(error("give width and height for flat pixel buffers".to_string())),
+    };
+    let pixels = buffer.to_vec(value.py())?;
+    RgbaImage::from_raw(width, height, pixels).ok_or_else(|| error(format!("the pixel buffer is too small for {}x{}", width, height)))
+}
+
+// Runs one job. images names images for the tile list to take tiles from, as file names or pixels with a width and height.
+#[pyfunction]
+#[pyo3(signature = (input, width=None, height=None, tiles=None, images=None, **options))]
+fn assemble<'py>(py : Python<'py>, input : &Bound<'py, PyAny>, width : Option<u32>, height : Option<u32>, tiles : Option<Vec<Bound<'py, PyAny>>>,
+    images : Option<&Bound<'py, PyDict>>, options : Option<&Bound<'py, PyDict>>) -> PyResult<(Bound<'py, PyBytes>, u32, u32)>
+{
+    let mut job = Job::default();
+    for (key, value) in options.map(|options| options.iter().collect::<Vec<_>>()).unwrap_or_default()
+    {
+        job.set_option(&key.extract::<String>()?, &option_text(&value)?).map_err(error)?;
+    }
+    for tile in tiles.unwrap_or_default()
+    {
+        job.add_tile(&option_text(&tile)?).map_err(error)?;
+    }
+    let mut sources = HashMap::new();
+    for (name, value) in images.map(|images| images.iter().collect::<Vec<_>>()).unwrap_or_default()
+    {
+        let source = if let Ok((pixels, width, height)) = value.extract::<(Bound<PyAny>, u32, u32)>() { image(&pixels, Some(width), Some(height), &job)? } else { image(&value, None, None, &job)? };
+        sources.insert(name.extract::<String>()?, source);
+    }
+    // file names in the tile list that weren't given as images are loaded like on the command line
+    for filename in job.tile_sources()
+    {
+        if let Entry::Vacant(entry) = sources.entry(filename.clone())
+        {
+            entry.insert(load(&filename, &job)?);
+        }
+    }
+    
+    // files keep all their frames, like animated GIFs and Aseprite files on the command line
+    let out_img = if let Ok(filename) = input.extract::<String>()
+    {
+        py.allow_threads(|| job.run_file(&filename, &sources))
+    }
+    else
+    {
+        let in_img = image(input, width, height, &job)?;
+        py.allow_threads(|| job.run_image(&in_img, &sources))
+    };
+    let out_img = out_img.map_err(error)?;
+    Ok((PyBytes::new_bound(py, &out_img), out_img.width(), out_img.height()))
+}
+
+#[pymodule]
+fn autotyler(module : &Bound<PyModule>) -> PyResult<()>
+{
+    module.add_function(wrap_pyfunction!(assemble, module)?)?;
+    Ok(())
+}
@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+
+use image::Rgba;
+use image::RgbaImage;
+
+use crate::assemble::Settings;
+use crate::check;
+use crate::masks::Arrangement;
+use crate::variants;
+
+// Input tiles recovered from a finished sheet.
+pub struct Reversed
+{
+    pub input : RgbaImage,
+    // output pixels that don't match what the input tiles would generate there, by output cell
+    pub unexplained : Vec<((u32, u32), u32)>,
+    pub unexplained_total : u32,
+}
+
+// Works out the input tiles that would generate the given sheet, laid out as the arrangement says, with the given settings.
+// Every input pixel is copied to one or more places in the output, so it gets whatever colour most of those places have.
+// Output pixels that still come out different afterwards (hand-painted variations, or the wrong mode or edges) are counted.
+pub fn reverse(sheet : &RgbaImage, settings : &Settings, (width, height) : (u32, u32), arrangement : &Arrangement) -> Result<Reversed, String>
+{
+    let size = settings.size;
+    let (columns, rows) = (arrangement.columns, arrangement.rows);
+    if sheet.dimensions() != (columns*size, rows*size)
+    {
+        return Err(format!("a {} sheet of {} pixel tiles is {}x{}, got {}x{}", arrangement.name, size, columns*size, rows*size, sheet.width(), sheet.height()));
+    }
+    // moved to where the arrangement has each cell, like the output is
+    let (sources, _) = variants::rearrange(&check::trace((width, height), settings)?, size, &[], arrangement);
+    
+    let mut votes = HashMap::<(u32, u32), HashMap<Rgba<u8>, u32>>::new();
+    for (x, y, px) in sources.enumerate_pixels()
+    {
+        if let Some(source) = check::decode(px)
+        {
+            *votes.entry(source).or_default().entry(*sheet.get_pixel(x, y)).or_insert(0) += 1;
+        }
+    }
+    let mut input = RgbaImage::new(width, height);
+    for (source, colours) in votes
+    {
+        // ties go to the smallest colour so that the result doesn't depend on hash order
+        let (colour, _) = colours.into_iter().max_by_key(|&(colour, count)| (count, std::cmp::Reverse(colour.0))).unwrap();
+        input.put_pixel(source.0, source.1, colour);
+    }
+    
+    let mut unexplained = Vec::<((u32, u32), u32)>::new();
+    let mut unexplained_total = 0;
+    for (x, y, px) in sources.enumerate_pixels()
+    {
+        let expected = match check::decode(px)
+        {
+            Some(source) => *input.get_pixel(source.0, source.1),
+            None => Rgba([0, 0, 0, 0]),
+        };
+        if *sheet.get_pixel(x, y) != expected
+        {
+            let cell = (x/size, y/size);
+            match unexplained.iter_mut().find(|(c, _)| *c == cell)
+            {
+                Some((_, count)) => *count += 1,
+                None => unexplained.push((cell, 1)),
+            }
+            unexplained_total += 1;
+        }
+    }
+    unexplained.sort_by_key(|&((x, y), _)| (y, x));
+    Ok(Reversed { input, unexplained, unexplained_total })
+}
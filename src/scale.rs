@@ -0,0 +1,124 @@
+use image::GenericImage;
+use image::GenericImageView;
+use image::Rgba;
+use image::RgbaImage;
+
+// Scale2x (EPX): every pixel becomes 2x2, with corners taking the colour of the two neighbours next to them when those agree.
+fn scale2x(img : &RgbaImage) -> RgbaImage
+{
+    let (w, h) = img.dimensions();
+    let get = |x : i64, y : i64| *img.get_pixel(x.clamp(0, w as i64 - 1) as u32, y.clamp(0, h as i64 - 1) as u32);
+    let mut out = RgbaImage::new(w*2, h*2);
+    for y in 0..h as i64
+    {
+        for x in 0..w as i64
+        {
+            let (b, d, e, f, h) = (get(x, y - 1), get(x - 1, y), get(x, y), get(x + 1, y), get(x, y + 1));
+            let pick = |cond : bool, colour : Rgba<u8>| if cond { colour } else { e };
+            let parts = [
+                pick(d == b && b != f && d != h, d),
+                pick(b == f && b != d && f != h, f),
+                pick(d == h && d != b && h != f, d),
+                pick(h == f && d != h && b != f, f),
+            ];
+            for (i, px) in parts.iter().enumerate()
+            {
+                out.put_pixel(x as u32*2 + i as u32 % 2, y as u32*2 + i as u32 / 2, *px);
+            }
+        }
+    }
+    out
+}
+
+// Scale3x: the same idea as scale2x with 3x3 pixels, where the edge pixels also look at the diagonal neighbours.
+fn scale3x(img : &RgbaImage) -> RgbaImage
+{
+    let (w, h) = img.dimensions();
+    let get = |x : i64, y : i64| *img.get_pixel(x.clamp(0, w as i64 - 1) as u32, y.clamp(0, h as i64 - 1) as u32);
+    let mut out = RgbaImage::new(w*3, h*3);
+    for y in 0..h as i64
+    {
+        for x in 0..w as i64
+        {
+            let (a, b, c) = (get(x - 1, y - 1), get(x, y - 1), get(x + 1, y - 1));
+            let (d, e, f) = (get(x - 1, y), get(x, y), get(x + 1, y));
+            let (g, h, i) = (get(x - 1, y + 1), get(x, y + 1), get(x + 1, y + 1));
+            let pick = |cond : bool, colour : Rgba<u8>| if cond { colour } else { e };
+            let (top_left, top_right) = (d == b && b != f && d != h, b == f && b != d && f != h);
+            let (bottom_left, bottom_right) = (d == h && d != b && h != f, h == f && d != h && b != f);
+            let parts = [
+                pick(top_left, d),
+                pick((top_left && e != c) || (top_right && e != a), b),
+                pick(top_right, f),
+                pick((top_left && e != g) || (bottom_left && e != a), d),
+                e,
+                pick((top_right && e != i) || (bottom_right && e != c), f),
+                pick(bottom_left, d),
+                pick((bottom_left && e != i) || (bottom_right && e != g), h),
+                pick(bottom_right, f),
+            ];
+            for (n, px) in parts.iter().enumerate()
+            {
+                out.put_pixel(x as u32*3 + n as u32 % 3, y as u32*3 + n as u32 / 3, *px);
+            }
+        }
+    }
+    out
+}
+
+fn nearest(img : &RgbaImage, factor : u32) -> RgbaImage
+{
+    RgbaImage::from_fn(img.width()*factor, img.height()*factor, |x, y| *img.get_pixel(x/factor, y/factor))
+}
+
+// Scales a sheet up by a whole factor, one cell at a time so that cells never bleed into each other.
+// scaler is nearest, or scale2x, which uses scale2x and scale3x passes and so needs a factor made of 2s and 3s.
+pub fn scale(sheet : &RgbaImage, size : u32, factor : u32, scaler : &str) -> Result<RgbaImage, String>
+{
+    if factor == 0
+    {
+        return Err("scale must be at least 1".to_string());
+    }
+    if factor == 1
+    {
+        return Ok(sheet.clone());
+    }
+    let mut passes = Vec::new();
+    if scaler == "scale2x"
+    {
+        let mut rest = factor;
+        while rest.is_multiple_of(3)
+        {
+            passes.push(3);
+            rest /= 3;
+        }
+        while rest.is_multiple_of(2)
+        {
+            passes.push(2);
+            rest /= 2;
+        }
+        if rest != 1
+        {
+            return Err(format!("scaler=scale2x can only scale by products of 2 and 3, not {}", factor));
+        }
+    }
+    
+    let mut img = RgbaImage::new(sheet.width()*factor, sheet.height()*factor);
+    for cy in 0..sheet.height()/size
+    {
+        for cx in 0..sheet.width()/size
+        {
+            let mut cell = sheet.view(cx*size, cy*size, size, size).to_image();
+            if passes.is_empty()
+            {
+                cell = nearest(&cell, factor);
+            }
+            for pass in &passes
+            {
+                cell = if *pass == 3 { scale3x(&cell) } else { scale2x(&cell) };
+            }
+            img.copy_from(&cell, cx*size*factor, cy*size*factor).unwrap();
+        }
+    }
+    Ok(img)
+}
@@ -0,0 +1,247 @@
+use crate::masks::*;
+use crate::padding::Layout;
+use crate::variants;
+use crate::variants::VariantCell;
+
+// The script of RuleTile in unity's 2D Tilemap Extras package.
+const RULE_TILE_GUID : &str = "9d1514134bc4fbd41bb739b1b9a49231";
+
+fn fnv1a(text : &str, seed : u64) -> u64
+{
+    let mut hash = 0xcbf2_9ce4_8422_2325 ^ seed;
+    for byte in text.bytes()
+    {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+// A made-up but stable asset GUID, so that regenerating an output doesn't break references to it in unity.
+pub fn guid(text : &str) -> String
+{
+    format!("{:016x}{:016x}", fnv1a(text, 0), fnv1a(text, 0x9e37_79b9_7f4a_7c15))
+}
+
+// The fileID of the sprite of a cell in a frame. These follow unity's own numbering for sliced sprites.
+fn sprite_id(layout : &Layout, (x, y) : (u32, u32), frame : u32) -> u64
+{
+    21_300_000 + 2*(((frame*layout.rows + y)*layout.columns + x) as u64)
+}
+
+fn sprite_name(name : &str, (x, y) : (u32, u32), frame : u32, layout : &Layout) -> String
+{
+    if layout.frames > 1 { format!("{}_{}_{}_{}", name, frame, x, y) } else { format!("{}_{}_{}", name, x, y) }
+}
+
+// The .meta file for the output image, slicing it into a sprite for every cell of every frame.
+// Unity measures sprite rects from the bottom of the image. Blank cells get no sprite.
+pub fn sprite_meta(name : &str, image_guid : &str, layout : &Layout, variants : &[VariantCell]) -> String
+{
+    let mut table = String::new();
+    let mut sprites = String::new();
+    for frame in 0..layout.frames
+    {
+        for y in 0..layout.rows
+        {
+            for x in 0..layout.columns
+            {
+                if variants::mask_of(layout.arrangement, variants, (x, y)) == 0
+                {
+                    continue;
+                }
+                let sprite = sprite_name(name, (x, y), frame, layout);
+                let id = sprite_id(layout, (x, y), frame);
+                let (left, top) = layout.cell_origin((x, y), frame);
+                table += &format!("  - first:\n      213: {}\n    second: {}\n", id, sprite);
+                sprites += &format!(
+"    - serializedVersion: 2
+      name: {}
+      rect:
+        serializedVersion: 2
+        x: {}
+        y: {}
+        width: {}
+        height: {}
+      alignment: 0
+      pivot: {{x: 0.5, y: 0.5}}
+      border: {{x: 0, y: 0, z: 0, w: 0}}
+      outline: []
+      physicsShape: []
+      tessellationDetail: 0
+      bones: []
+      spriteID: {}
+      internalID: {}
+      vertices: []
+      indices: 
+      edges: []
+      weights: []
+", sprite, left, layout.height() - top - layout.size, layout.size, layout.size, guid(&format!("{}:{}", image_guid, sprite)), id);
+            }
+        }
+    }
+    format!(
+"fileFormatVersion: 2
+guid: {}
+TextureImporter:
+  internalIDToNameTable:
+{}  externalObjects: {{}}
+  serializedVersion: 11
+  mipmaps:
+    mipMapMode: 0
+    enableMipMap: 0
+  isReadable: 0
+  textureFormat: 1
+  maxTextureSize: 2048
+  textureSettings:
+    serializedVersion: 2
+    filterMode: 0
+    aniso: 1
+    mipBias: 0
+    wrapU: 1
+    wrapV: 1
+    wrapW: 1
+  nPOTScale: 0
+  lightmap: 0
+  compressionQuality: 50
+  spriteMode: 2
+  spriteExtrude: 1
+  spriteMeshType: 0
+  alignment: 0
+  spritePivot: {{x: 0.5, y: 0.5}}
+  spritePixelsToUnits: {}
+  spriteBorder: {{x: 0, y: 0, z: 0, w: 0}}
+  spriteGenerateFallbackPhysicsShape: 1
+  alphaUsage: 1
+  alphaIsTransparency: 1
+  textureType: 8
+  textureShape: 1
+  platformSettings:
+  - serializedVersion: 3
+    buildTarget: DefaultTexturePlatform
+    maxTextureSize: 2048
+    resizeAlgorithm: 0
+    textureFormat: -1
+    textureCompression: 0
+    compressionQuality: 50
+    crunchedCompression: 0
+  spriteSheet:
+    serializedVersion: 2
+    sprites:
+{}    outline: []
+    physicsShape: []
+    bones: []
+    spriteID: 
+    internalID: 0
+    vertices: []
+    indices: 
+    edges: []
+    weights: []
+    secondaryTextures: []
+  spritePackingTag: 
+  pSDRemoveMatte: 0
+  pSDShowRemoveMatteOption: 0
+  userData: 
+  assetBundleName: 
+  assetBundleVariant: 
+", image_guid, table, layout.size, sprites)
+}
+
+// What a rule wants of each neighbour, in unity's order (left to right, top row first, y going up):
+// 1 for a tile of this kind, 2 for anything else, 0 when it doesn't matter.
+// Corners only matter when both sides next to them are filled, like in godot's 3x3 minimal mode.
+pub fn neighbours(mask : u16) -> [u32; 8]
+{
+    let state = |bit : u16| if mask & bit != 0 { 1 } else { 2 };
+    let corner = |bit : u16, sides : u16| if mask & sides == sides { state(bit) } else { 0 };
+    [
+        corner(TOP_LEFT, TOP | LEFT), state(TOP), corner(TOP_RIGHT, TOP | RIGHT),
+        state(LEFT), state(RIGHT),
+        corner(BOTTOM_LEFT, BOTTOM | LEFT), state(BOTTOM), corner(BOTTOM_RIGHT, BOTTOM | RIGHT),
+    ]
+}
+
+// A RuleTile asset with a rule for every bitmask in the sheet. Cells that share a bitmask become one rule with a random
+// output (unity picks between them evenly), and animated sheets get animated rules at 1000/frame_duration sprites per second
+// (with the tilemap's frame rate left at 1). The fully surrounded cell is the default sprite.
+pub fn rule_tile(name : &str, image_guid : &str, layout : &Layout, variants : &[VariantCell], frame_duration : u32) -> String
+{
+    let sprite_ref = |cell : (u32, u32), frame : u32| format!("{{fileID: {}, guid: {}, type: 3}}", sprite_id(layout, cell, frame), image_guid);
+    // every bitmask, in the order they first show up, with its cells
+    let mut rules = Vec::<(u16, Vec<(u32, u32)>)>::new();
+    for y in 0..layout.rows
+    {
+        for x in 0..layout.columns
+        {
+            let mask = variants::mask_of(layout.arrangement, variants, (x, y));
+            if mask == 0
+            {
+                continue;
+            }
+            match rules.iter_mut().find(|rule| rule.0 == mask)
+            {
+                Some(rule) => rule.1.push((x, y)),
+                None => rules.push((mask, vec!((x, y)))),
+            }
+        }
+    }
+    let positions = [(-1, 1), (0, 1), (1, 1), (-1, 0), (1, 0), (-1, -1), (0, -1), (1, -1)];
+    let full = rules.iter().find(|rule| rule.0 == 0x1ff).or_else(|| rules.first()).map(|rule| rule.1[0]).unwrap_or((0, 0));
+    
+    let mut ret = format!(
+"%YAML 1.1
+%TAG !u! tag:unity3d.com,2011:
+--- !u!114 &11400000
+MonoBehaviour:
+  m_ObjectHideFlags: 0
+  m_CorrespondingSourceObject: {{fileID: 0}}
+  m_PrefabInstance: {{fileID: 0}}
+  m_PrefabAsset: {{fileID: 0}}
+  m_GameObject: {{fileID: 0}}
+  m_Enabled: 1
+  m_EditorHideFlags: 0
+  m_Script: {{fileID: 11500000, guid: {}, type: 3}}
+  m_Name: {}
+  m_EditorClassIdentifier: 
+  m_DefaultSprite: {}
+  m_DefaultGameObject: {{fileID: 0}}
+  m_DefaultColliderType: 1
+  m_TilingRules:
+", RULE_TILE_GUID, name, sprite_ref(full, 0));
+    let speed = 1000.0/frame_duration.max(1) as f64;
+    for (id, (mask, cells)) in rules.iter().enumerate()
+    {
+        // animated rules go through the frames of the first cell
+        let (output, sprites) = if layout.frames > 1
+        {
+            (2, (0..layout.frames).map(|frame| sprite_ref(cells[0], frame)).collect::<Vec<_>>())
+        }
+        else
+        {
+            (if cells.len() > 1 { 1 } else { 0 }, cells.iter().map(|&cell| sprite_ref(cell, 0)).collect())
+        };
+        let states = neighbours(*mask);
+        ret += &format!("  - m_Id: {}\n    m_Sprites:\n", id);
+        for sprite in &sprites
+        {
+            ret += &format!("    - {}\n", sprite);
+        }
+        ret += &format!(
+"    m_GameObject: {{fileID: 0}}
+    m_MinAnimationSpeed: {}
+    m_MaxAnimationSpeed: {}
+    m_PerlinScale: 0.5
+    m_Output: {}
+    m_ColliderType: 1
+    m_RandomTransform: 0
+    m_Neighbors: {}
+    m_NeighborPositions:
+", speed, speed, output, states.iter().map(|state| format!("{:02x}000000", state)).collect::<String>());
+        for (x, y) in &positions
+        {
+            ret += &format!("    - {{x: {}, y: {}, z: 0}}\n", x, y);
+        }
+        ret += "    m_RuleTransform: 0\n";
+    }
+    ret
+}
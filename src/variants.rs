@@ -0,0 +1,143 @@
+use image::GenericImage;
+use image::GenericImageView;
+use image::RgbaImage;
+
+use crate::assemble;
+use crate::assemble::Settings;
+use crate::masks;
+
+// One of several cells with the same bitmask, to be picked at random.
+#[derive(Clone)]
+pub struct VariantCell
+{
+    pub cell : (u32, u32),
+    pub mask : u16,
+    // chance of this cell being picked out of all the cells with its bitmask
+    pub probability : f64,
+}
+
+// Tile groups as given in the tile list: the first tile of each group is the one in settings.origin_tile_list,
+// the rest are alternatives for it. Each tile has a weight. Offsets must already be applied.
+pub type VariantGroups = [Vec<((u32, u32), f64)>];
+
+fn cells_differ(a : &RgbaImage, b : &RgbaImage, (x, y) : (u32, u32), size : u32) -> bool
+{
+    (0..size).any(|iy| (0..size).any(|ix| a.get_pixel(x*size + ix, y*size + iy) != b.get_pixel(x*size + ix, y*size + iy)))
+}
+
+// Builds the 12x4 sheet, plus extra rows of 12 below it holding every cell that comes out different when one of the
+// tile groups uses one of its alternatives instead. Returns every cell with the same bitmask as another one, with its
+// chance of being picked (its own weight against the others, the normal cell getting the weight of the first tile).
+pub fn assemble_variants(in_img : &RgbaImage, settings : &Settings, groups : &VariantGroups) -> (RgbaImage, Vec<VariantCell>)
+{
+    let size = settings.size;
+    let base = assemble::assemble(in_img, settings);
+    
+    // (cell in the 12x4 sheet, weight, sheet to copy it from)
+    let mut extra = Vec::<((u32, u32), f64, usize)>::new();
+    let mut base_weights = Vec::<((u32, u32), f64)>::new();
+    let mut sheets = Vec::<RgbaImage>::new();
+    for (i, group) in groups.iter().enumerate()
+    {
+        for &(tile, weight) in group.iter().skip(1)
+        {
+            let mut variant_settings = settings.clone();
+            variant_settings.origin_tile_list[i] = tile;
+            let sheet = assemble::assemble(in_img, &variant_settings);
+            for y in 0..4
+            {
+                for x in 0..12
+                {
+                    if masks::godot_mask((x, y)) == 0 || !cells_differ(&base, &sheet, (x, y), size)
+                    {
+                        continue;
+                    }
+                    extra.push(((x, y), weight, sheets.len()));
+                    // cells changed by several groups keep the weight of the first one
+                    if !base_weights.iter().any(|(cell, _)| *cell == (x, y))
+                    {
+                        base_weights.push(((x, y), group[0].1));
+                    }
+                }
+            }
+            sheets.push(sheet);
+        }
+    }
+    
+    let extra_rows = (extra.len() as u32).div_ceil(12);
+    let mut out_img = RgbaImage::new(12*size, (4 + extra_rows)*size);
+    out_img.copy_from(&base, 0, 0).unwrap();
+    let mut cells = base_weights.iter().map(|&(cell, weight)| (cell, masks::godot_mask(cell), weight)).collect::<Vec<_>>();
+    for (i, &((x, y), weight, sheet)) in extra.iter().enumerate()
+    {
+        let cell = (i as u32 % 12, 4 + i as u32/12);
+        out_img.copy_from(&sheets[sheet].view(x*size, y*size, size, size), cell.0*size, cell.1*size).unwrap();
+        cells.push((cell, masks::godot_mask((x, y)), weight));
+    }
+    
+    let variants = cells.iter().map(|&(cell, mask, weight)|
+    {
+        let total : f64 = cells.iter().filter(|other| other.1 == mask).map(|other| other.2).sum();
+        VariantCell { cell, mask, probability : if total > 0.0 { weight/total } else { 0.0 } }
+    }).collect();
+    (out_img, variants)
+}
+
+// Moves the cells of a sheet made by assemble_variants() to where another arrangement wants them,
+// with the alternative cells in extra rows below, as many to a row as the arrangement is wide.
+pub fn rearrange(sheet : &RgbaImage, size : u32, variants : &[VariantCell], arrangement : &masks::Arrangement) -> (RgbaImage, Vec<VariantCell>)
+{
+    if arrangement.name == masks::GODOT.name
+    {
+        return (sheet.clone(), variants.to_vec());
+    }
+    let extra = variants.iter().filter(|variant| variant.cell.1 >= 4).collect::<Vec<_>>();
+    let extra_rows = (extra.len() as u32).div_ceil(arrangement.columns);
+    let mut out_img = RgbaImage::new(arrangement.columns*size, (arrangement.rows + extra_rows)*size);
+    for y in 0..4
+    {
+        for x in 0..12
+        {
+            if let Some((cx, cy)) = arrangement.cell(masks::godot_mask((x, y)))
+            {
+                out_img.copy_from(&sheet.view(x*size, y*size, size, size), cx*size, cy*size).unwrap();
+            }
+        }
+    }
+    for (i, variant) in extra.iter().enumerate()
+    {
+        let (cx, cy) = (i as u32 % arrangement.columns, arrangement.rows + i as u32/arrangement.columns);
+        out_img.copy_from(&sheet.view(variant.cell.0*size, variant.cell.1*size, size, size), cx*size, cy*size).unwrap();
+    }
+    let mut extra_index = 0;
+    let variants = variants.iter().map(|variant|
+    {
+        let cell = if variant.cell.1 < 4
+        {
+            arrangement.cell(variant.mask).unwrap()
+        }
+        else
+        {
+            extra_index += 1;
+            ((extra_index - 1) % arrangement.columns, arrangement.rows + (extra_index - 1)/arrangement.columns)
+        };
+        VariantCell { cell, ..variant.clone() }
+    }).collect();
+    (out_img, variants)
+}
+
+// How many of the cells are alternative cells in the extra rows below the arrangement.
+pub fn extra_count(arrangement : &masks::Arrangement, variants : &[VariantCell]) -> usize
+{
+    variants.iter().filter(|variant| variant.cell.1 >= arrangement.rows).count()
+}
+
+// The bitmask of any cell of a sheet made by assemble_variants() (and rearrange(), with its arrangement).
+pub fn mask_of(arrangement : &masks::Arrangement, variants : &[VariantCell], cell : (u32, u32)) -> u16
+{
+    if cell.1 < arrangement.rows
+    {
+        return arrangement.mask(cell);
+    }
+    variants.iter().find(|variant| variant.cell == cell).map(|variant| variant.mask).unwrap_or(0)
+}
@@ -0,0 +1,133 @@
+use image::Rgba;
+use image::RgbaImage;
+
+use crate::masks::*;
+
+#[derive(Clone, Copy)]
+enum Side
+{
+    Left,
+    Top,
+    Right,
+    Bottom,
+}
+
+// The bits that decide what the pixels along one side of a cell look like, or None if that side is open.
+// Two cells with the same bits here can sit next to the same neighbour on that side, so they must look the same along it.
+// Nothing of the same terrain sits next to an open side, so there is no seam there to keep.
+fn side_bits(mask : u16, side : Side) -> Option<u16>
+{
+    let (own, bits) = match side
+    {
+        Side::Left => (LEFT, TOP | TOP_LEFT | LEFT | BOTTOM_LEFT | BOTTOM),
+        Side::Top => (TOP, LEFT | TOP_LEFT | TOP | TOP_RIGHT | RIGHT),
+        Side::Right => (RIGHT, TOP | TOP_RIGHT | RIGHT | BOTTOM_RIGHT | BOTTOM),
+        Side::Bottom => (BOTTOM, LEFT | BOTTOM_LEFT | BOTTOM | BOTTOM_RIGHT | RIGHT),
+    };
+    if mask & own == 0 { None } else { Some(mask & bits) }
+}
+
+fn side_name(side : Side) -> &'static str
+{
+    match side
+    {
+        Side::Left => "left column",
+        Side::Top => "top row",
+        Side::Right => "right column",
+        Side::Bottom => "bottom row",
+    }
+}
+
+// The pixels along one side of a cell, as (x, y, pixel) in sheet coordinates, leaving out the ends that are part of the
+// outline of an open side across it. The 3x3 and 4x4 inputs draw that outline as one picture across their corner and edge
+// tiles rather than the same in every tile, so it only lines up with the cells it was drawn next to.
+fn side_pixels(img : &RgbaImage, size : u32, (left, top, right, bottom) : (u32, u32, u32, u32), (cx, cy) : (u32, u32), mask : u16, side : Side) -> Vec<(u32, u32, Rgba<u8>)>
+{
+    let (before, after, open_before, open_after) = match side
+    {
+        Side::Left | Side::Right => (top, bottom, mask & TOP == 0, mask & BOTTOM == 0),
+        Side::Top | Side::Bottom => (left, right, mask & LEFT == 0, mask & RIGHT == 0),
+    };
+    (0..size).filter(|&i| !((open_before && i < before) || (open_after && i >= size.saturating_sub(after)))).map(|i|
+    {
+        let (x, y) = match side
+        {
+            Side::Left => (0, i),
+            Side::Top => (i, 0),
+            Side::Right => (size-1, i),
+            Side::Bottom => (i, size-1),
+        };
+        let (x, y) = (cx*size + x, cy*size + y);
+        (x, y, *img.get_pixel(x, y))
+    }).collect()
+}
+
+// Checks that a sheet laid out as the arrangement says, made with the given edges, is internally consistent:
+// - the blank cells are fully transparent
+// - cells that can sit next to the same neighbour have identical pixels along that seam, apart from the outlines of open sides
+// Returns a description of every problem found.
+pub fn verify(img : &RgbaImage, size : u32, edges : (u32, u32, u32, u32), arrangement : &Arrangement) -> Vec<String>
+{
+    let mut problems = Vec::new();
+    let (columns, rows) = (arrangement.columns, arrangement.rows);
+    if size == 0 || img.dimensions() != (columns*size, rows*size)
+    {
+        problems.push(format!("expected a {}x{} {} sheet for size {}, got {}x{}", columns*size, rows*size, arrangement.name, size, img.width(), img.height()));
+        return problems;
+    }
+    
+    let cells = (0..rows).flat_map(|y| (0..columns).map(move |x| (x, y))).collect::<Vec<_>>();
+    
+    for &cell in &cells
+    {
+        if arrangement.mask(cell) != 0
+        {
+            continue;
+        }
+        for iy in 0..size
+        {
+            for ix in 0..size
+            {
+                let (x, y) = (cell.0*size + ix, cell.1*size + iy);
+                if img.get_pixel(x, y)[3] != 0
+                {
+                    problems.push(format!("blank cell {:?} is not transparent at pixel ({}, {})", cell, x, y));
+                }
+            }
+        }
+    }
+    
+    for &side in &[Side::Left, Side::Top, Side::Right, Side::Bottom]
+    {
+        // the first cell seen with each set of side bits, to compare the others against
+        let mut reference = Vec::<(u16, (u32, u32))>::new();
+        for &cell in &cells
+        {
+            let mask = arrangement.mask(cell);
+            let bits = match side_bits(mask, side)
+            {
+                Some(bits) => bits,
+                None => continue,
+            };
+            let other = match reference.iter().find(|(other_bits, _)| *other_bits == bits)
+            {
+                Some(&(_, other)) => other,
+                None =>
+                {
+                    reference.push((bits, cell));
+                    continue;
+                }
+            };
+            // the bits across the side are part of the side bits, so both cells leave out the same pixels
+            let pixels = side_pixels(img, size, edges, cell, mask, side);
+            let other_pixels = side_pixels(img, size, edges, other, arrangement.mask(other), side);
+            if let Some(((x, y, _), (other_x, other_y, _))) = pixels.iter().zip(other_pixels.iter()).find(|(a, b)| a.2 != b.2)
+            {
+                problems.push(format!("{} of cell {:?} differs from cell {:?}, which has the same neighbours on that side, at pixel ({}, {}) vs ({}, {})",
+                    side_name(side), cell, other, x, y, other_x, other_y));
+            }
+        }
+    }
+    
+    problems
+}
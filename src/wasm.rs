@@ -0,0 +1,132 @@
+// A plain C ABI over the assembly core, made for wasm32-unknown-unknown (no wasm-bindgen needed) but usable from anything
+// that can call C. The host allocates buffers in our memory with autotyler_alloc(), fills in the input pixels and options,
+// calls autotyler_assemble() and reads the output out of the result, then frees everything. Every pointer is into our memory,
+// and has to point to as much memory as the functions say.
+#![allow(clippy::missing_safety_doc)]
+
+use image::RgbaImage;
+
+use crate::assemble;
+use crate::job::Edges;
+use crate::job::Job;
+
+// The mode field of AssembleOptions indexes this, in the order its comment lists them.
+pub const MODES : [&str; 7] = ["basic", "basic_border", "3x3", "3x3plus", "4x4", "4x4plus", "minitiles"];
+
+/// What the options on the command line would say. On wasm32 every field is 4 bytes, so this is 11 u32s in a row.
+#[repr(C)]
+pub struct AssembleOptions
+{
+    /// 0 to 6 for basic, basic_border, 3x3, 3x3plus, 4x4, 4x4plus, minitiles
+    pub mode : u32,
+    pub size : u32,
+    /// left, top, right, bottom, all 0 for the default of half the tile size
+    pub edges : [u32; 4],
+    pub border : u32,
+    /// in tiles
+    pub offset_x : u32,
+    pub offset_y : u32,
+    /// tile_count x,y pairs, or null for the default tile list
+    pub tile_count : u32,
+    pub tiles : *const u32,
+}
+
+/// Either the output pixels or an error message, never both. On wasm32 this is 5 u32s in a row.
+#[repr(C)]
+pub struct AssembleResult
+{
+    /// width*height*4 bytes of RGBA
+    pub pixels : *mut u8,
+    pub width : u32,
+    pub height : u32,
+    /// UTF-8, not null terminated
+    pub error : *mut u8,
+    pub error_len : u32,
+}
+
+fn leak(bytes : Vec<u8>) -> *mut u8
+{
+    Box::into_raw(bytes.into_boxed_slice()) as *mut u8
+}
+
+unsafe fn unleak(ptr : *mut u8, len : usize)
+{
+    if !ptr.is_null()
+    {
+        drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(ptr, len)));
+    }
+}
+
+fn assemble_buffer(options : &AssembleOptions, rgba : &[u8], width : u32, height : u32, tiles : &[u32]) -> Result<RgbaImage, String>
+{
+    let in_img = RgbaImage::from_raw(width, height, rgba.to_vec()).ok_or_else(|| "the input buffer is too small for its dimensions".to_string())?;
+    let mut job = Job
+    {
+        mode : MODES.get(options.mode as usize).ok_or_else(|| format!("unknown mode {}", options.mode))?.to_string(),
+        size : Some(options.size),
+        edges : if options.edges == [0; 4] { Edges::Default } else { Edges::Measures(options.edges.to_vec()) },
+        offset : (options.offset_x, options.offset_y),
+        border : options.border,
+        ..Job::default()
+    };
+    for tile in tiles.chunks(2)
+    {
+        job.add_tile(&format!("{},{}", tile[0], tile[1]))?;
+    }
+    // settings() checks everything that would make assemble() panic (see assemble::check()), which can't be caught on wasm
+    let settings = job.settings(&in_img)?;
+    Ok(assemble::assemble(&in_img, &settings))
+}
+
+/// Allocates len bytes for the host to write into.
+#[no_mangle]
+pub extern "C" fn autotyler_alloc(len : usize) -> *mut u8
+{
+    leak(vec!(0; len))
+}
+
+/// ptr must come from autotyler_alloc() with the same len.
+#[no_mangle]
+pub unsafe extern "C" fn autotyler_free(ptr : *mut u8, len : usize)
+{
+    unleak(ptr, len);
+}
+
+unsafe fn assemble_pointers(options : *const AssembleOptions, rgba : *const u8, width : u32, height : u32) -> Result<RgbaImage, String>
+{
+    if options.is_null()
+    {
+        return Err("got null options".to_string());
+    }
+    if rgba.is_null()
+    {
+        return Err("got a null pixel buffer".to_string());
+    }
+    let options = &*options;
+    let len = (width as usize).checked_mul(height as usize).and_then(|len| len.checked_mul(4)).ok_or_else(|| format!("{}x{} is too large", width, height))?;
+    let rgba = std::slice::from_raw_parts(rgba, len);
+    let tiles = if options.tiles.is_null() { &[][..] } else { std::slice::from_raw_parts(options.tiles, options.tile_count as usize*2) };
+    assemble_buffer(options, rgba, width, height, tiles)
+}
+
+/// Runs one mode over width*height*4 bytes of RGBA. The result has to be freed with autotyler_free_result(). Null
+/// pointers give an error in the result, like everything else that goes wrong.
+#[no_mangle]
+pub unsafe extern "C" fn autotyler_assemble(options : *const AssembleOptions, rgba : *const u8, width : u32, height : u32) -> *mut AssembleResult
+{
+    let result = match assemble_pointers(options, rgba, width, height)
+    {
+        Ok(out_img) => AssembleResult { width : out_img.width(), height : out_img.height(), pixels : leak(out_img.into_raw()), error : std::ptr::null_mut(), error_len : 0 },
+        Err(err) => AssembleResult { pixels : std::ptr::null_mut(), width : 0, height : 0, error_len : err.len() as u32, error : leak(err.into_bytes()) },
+    };
+    Box::into_raw(Box::new(result))
+}
+
+/// result must come from autotyler_assemble() and not have been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn autotyler_free_result(result : *mut AssembleResult)
+{
+    let result = Box::from_raw(result);
+    unleak(result.pixels, result.width as usize*result.height as usize*4);
+    unleak(result.error, result.error_len as usize);
+}
@@ -0,0 +1,61 @@
+use std::collections::HashMap;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
+
+use crate::job;
+
+fn modified_times(filenames : &[String]) -> HashMap<String, Option<SystemTime>>
+{
+    filenames.iter().map(|filename| (filename.clone(), std::fs::metadata(filename).and_then(|meta| meta.modified()).ok())).collect()
+}
+
+// Keeps running the jobs given by the arguments whenever the config file, any image the jobs read or any preview map changes.
+// Errors (like failing to decode a half-written image) are printed and the next change is waited for.
+pub fn watch(args : &[String]) -> !
+{
+    let config_filename = if args.len() == 2 && args[0] == "--config" { Some(args[1].clone()) } else { None };
+    let mut watched = Vec::<String>::new();
+    let mut last_times = HashMap::new();
+    let mut first = true;
+    loop
+    {
+        let times = modified_times(&watched);
+        if first || times != last_times
+        {
+            // give whatever is writing the files a moment to finish
+            if !first
+            {
+                thread::sleep(Duration::from_millis(100));
+            }
+            first = false;
+            
+            let start = Instant::now();
+            match job::config_from_args(args)
+            {
+                Ok(config) =>
+                {
+                    watched = config.jobs.iter().map(|job| job.in_filename.clone()).collect();
+                    watched.extend(config.jobs.iter().flat_map(|job| job.frame_filenames.iter().cloned()));
+                    watched.extend(config.jobs.iter().flat_map(|job| job.tile_sources()));
+                    watched.extend(config.jobs.iter().map(|job| job.map_filename.clone()).filter(|filename| !filename.is_empty()));
+                    match job::run_config(&config)
+                    {
+                        Ok(()) => println!("rebuilt {} job(s) in {} ms", config.jobs.len(), start.elapsed().as_millis()),
+                        Err(err) => println!("rebuild failed: {}", err),
+                    }
+                }
+                Err(err) => println!("rebuild failed: {}", err),
+            }
+            if let Some(filename) = &config_filename
+            {
+                watched.push(filename.clone());
+            }
+            watched.sort();
+            watched.dedup();
+            last_times = modified_times(&watched);
+        }
+        thread::sleep(Duration::from_millis(250));
+    }
+}
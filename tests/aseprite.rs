@@ -0,0 +1,228 @@
+use image::Rgba;
+
+use autotyler::aseprite::Aseprite;
+use autotyler::aseprite::Options;
+
+// chunk type, data
+type Chunk = (u16, Vec<u8>);
+
+// Just enough of an Aseprite writer to build test files. Frames are (duration, chunks).
+struct Writer
+{
+    frames : Vec<(u16, Vec<Chunk>)>,
+}
+
+fn string(text : &str) -> Vec<u8>
+{
+    let mut ret = (text.len() as u16).to_le_bytes().to_vec();
+    ret.extend_from_slice(text.as_bytes());
+    ret
+}
+
+// flags, type, child level, opacity, name
+fn layer(flags : u16, layer_type : u16, child_level : u16, opacity : u8, name : &str) -> Chunk
+{
+    let mut data = Vec::new();
+    for word in &[flags, layer_type, child_level, 0, 0, 0]
+    {
+        data.extend_from_slice(&word.to_le_bytes());
+    }
+    data.extend_from_slice(&[opacity, 0, 0, 0]);
+    data.extend(string(name));
+    (0x2004, data)
+}
+
+fn cel_header(layer : u16, (x, y) : (i16, i16), cel_type : u16) -> Vec<u8>
+{
+    let mut data = Vec::new();
+    data.extend_from_slice(&layer.to_le_bytes());
+    data.extend_from_slice(&x.to_le_bytes());
+    data.extend_from_slice(&y.to_le_bytes());
+    data.push(255);
+    data.extend_from_slice(&cel_type.to_le_bytes());
+    data.extend_from_slice(&[0; 7]);
+    data
+}
+
+// a compressed cel filled with one colour
+fn cel(layer : u16, position : (i16, i16), (width, height) : (u16, u16), colour : [u8; 4]) -> Chunk
+{
+    let mut data = cel_header(layer, position, 2);
+    data.extend_from_slice(&width.to_le_bytes());
+    data.extend_from_slice(&height.to_le_bytes());
+    let pixels = (0..width as usize*height as usize).flat_map(|_| colour.to_vec()).collect::<Vec<_>>();
+    data.extend(miniz_oxide::deflate::compress_to_vec_zlib(&pixels, 6));
+    (0x2005, data)
+}
+
+fn linked_cel(layer : u16, position : (i16, i16), frame : u16) -> Chunk
+{
+    let mut data = cel_header(layer, position, 1);
+    data.extend_from_slice(&frame.to_le_bytes());
+    (0x2005, data)
+}
+
+fn tags(list : &[(&str, u16, u16)]) -> Chunk
+{
+    let mut data = (list.len() as u16).to_le_bytes().to_vec();
+    data.extend_from_slice(&[0; 8]);
+    for (name, from, to) in list
+    {
+        data.extend_from_slice(&from.to_le_bytes());
+        data.extend_from_slice(&to.to_le_bytes());
+        data.extend_from_slice(&[0; 13]);
+        data.extend(string(name));
+    }
+    (0x2018, data)
+}
+
+impl Writer
+{
+    fn write(&self, width : u16, height : u16) -> Vec<u8>
+    {
+        let mut frames = Vec::new();
+        for (duration, chunks) in &self.frames
+        {
+            let mut body = Vec::new();
+            for (chunk_type, data) in chunks
+            {
+                body.extend_from_slice(&(data.len() as u32 + 6).to_le_bytes());
+                body.extend_from_slice(&chunk_type.to_le_bytes());
+                body.extend_from_slice(data);
+            }
+            frames.extend_from_slice(&(body.len() as u32 + 16).to_le_bytes());
+            frames.extend_from_slice(&0xF1FAu16.to_le_bytes());
+            frames.extend_from_slice(&(chunks.len() as u16).to_le_bytes());
+            frames.extend_from_slice(&duration.to_le_bytes());
+            frames.extend_from_slice(&[0, 0]);
+            frames.extend_from_slice(&(chunks.len() as u32).to_le_bytes());
+            frames.extend(body);
+        }
+        let mut header = Vec::new();
+        header.extend_from_slice(&(frames.len() as u32 + 128).to_le_bytes());
+        header.extend_from_slice(&0xA5E0u16.to_le_bytes());
+        for word in &[self.frames.len() as u16, width, height, 32]
+        {
+            header.extend_from_slice(&word.to_le_bytes());
+        }
+        // layer opacity is valid
+        header.extend_from_slice(&1u32.to_le_bytes());
+        header.resize(128, 0);
+        header.extend(frames);
+        header
+    }
+}
+
+fn test_file() -> Vec<u8>
+{
+    let red = [255, 0, 0, 255];
+    let blue = [0, 0, 255, 255];
+    let green = [0, 255, 0, 255];
+    Writer
+    {
+        frames : vec!(
+            (100, vec!(
+                layer(1, 0, 0, 255, "ground"),
+                layer(1, 0, 0, 255, "details"),
+                layer(0, 1, 0, 255, "hidden group"),
+                layer(1, 0, 1, 255, "in hidden group"),
+                cel(0, (0, 0), (4, 4), red),
+                cel(1, (2, 2), (4, 4), blue),
+                cel(3, (0, 0), (4, 4), green),
+                tags(&[("first", 0, 0), ("rest", 1, 2)]),
+            )),
+            (200, vec!(
+                linked_cel(0, (0, 0), 0),
+                cel(1, (0, 0), (1, 1), blue),
+            )),
+            (300, vec!(
+                cel(0, (0, 0), (4, 4), blue),
+            )),
+        ),
+    }.write(4, 4)
+}
+
+#[test]
+fn layers_are_drawn_in_order()
+{
+    let ase = Aseprite::read(&test_file()).unwrap();
+    let frames = ase.render(&Options::default()).unwrap();
+    assert_eq!(frames.len(), 3);
+    assert_eq!(frames.iter().map(|frame| frame.1).collect::<Vec<_>>(), vec!(100, 200, 300));
+    
+    let first = &frames[0].0;
+    assert_eq!(first.dimensions(), (4, 4));
+    assert_eq!(*first.get_pixel(0, 0), Rgba([255, 0, 0, 255]));
+    // details on top, cut off at the edge of the canvas
+    assert_eq!(*first.get_pixel(3, 3), Rgba([0, 0, 255, 255]));
+    // the hidden group hides what's in it
+    assert_eq!(*first.get_pixel(1, 1), Rgba([255, 0, 0, 255]));
+    
+    // the linked cel repeats the first frame's ground
+    let second = &frames[1].0;
+    assert_eq!(*second.get_pixel(0, 0), Rgba([0, 0, 255, 255]));
+    assert_eq!(*second.get_pixel(3, 3), Rgba([255, 0, 0, 255]));
+}
+
+#[test]
+fn layers_tags_and_ranges_can_be_picked()
+{
+    let ase = Aseprite::read(&test_file()).unwrap();
+    let only = |options : Options| ase.render(&options).unwrap();
+    
+    let details = only(Options { layer : Some("details".to_string()), ..Options::default() });
+    assert_eq!(details[0].0.get_pixel(0, 0)[3], 0);
+    assert_eq!(*details[0].0.get_pixel(2, 2), Rgba([0, 0, 255, 255]));
+    
+    // picking a hidden group shows it anyway
+    let group = only(Options { layer : Some("hidden group".to_string()), ..Options::default() });
+    assert_eq!(*group[0].0.get_pixel(0, 0), Rgba([0, 255, 0, 255]));
+    
+    let rest = only(Options { tag : Some("rest".to_string()), ..Options::default() });
+    assert_eq!(rest.iter().map(|frame| frame.1).collect::<Vec<_>>(), vec!(200, 300));
+    let range = only(Options { frame_range : Some((2, 2)), ..Options::default() });
+    assert_eq!(range.len(), 1);
+    assert_eq!(*range[0].0.get_pixel(0, 0), Rgba([0, 0, 255, 255]));
+    
+    let separate = only(Options { separate_layers : true, ..Options::default() });
+    assert_eq!(separate[0].0.dimensions(), (8, 4));
+    assert_eq!(*separate[0].0.get_pixel(0, 0), Rgba([255, 0, 0, 255]));
+    assert_eq!(*separate[0].0.get_pixel(6, 2), Rgba([0, 0, 255, 255]));
+    
+    assert!(ase.render(&Options { tag : Some("missing".to_string()), ..Options::default() }).is_err());
+    assert!(ase.render(&Options { frame_range : Some((1, 5)), ..Options::default() }).is_err());
+    assert!(Aseprite::read(b"not an aseprite file at all").is_err());
+}
+
+// tests/fixtures/indexed.aseprite is an 8x4 indexed sprite with two frames, assembled by hand from Aseprite's file
+// format spec (docs/ase-file-specs.md in the Aseprite repository) rather than by the writer above, with the chunks
+// Aseprite 1.3 saves that the reader skips (the color profile and the old palette next to the new one). It isn't a file
+// saved by Aseprite itself, which would be a better fixture to swap in.
+// Palette: 0 black (the transparent index), 1 dark green, 2 grass, 3 dirt. The background layer has grass on the left
+// half, dirt on the right half and index 0 at 0,0, which stays black on a background. The "edges" layer has a 3x2 cel
+// of 1 1 0 / 0 1 1 at 2,1 in the first frame and a single dirt pixel at 0,0 in the second, where the background is linked.
+#[test]
+fn indexed_fixture()
+{
+    let data = std::fs::read(format!("{}/tests/fixtures/indexed.aseprite", env!("CARGO_MANIFEST_DIR"))).unwrap();
+    let frames = Aseprite::read(&data).unwrap().render(&Options::default()).unwrap();
+    assert_eq!(frames.iter().map(|frame| frame.1).collect::<Vec<_>>(), vec!(100, 150));
+    
+    let (black, green, grass, dirt) = (Rgba([0, 0, 0, 255]), Rgba([34, 85, 34, 255]), Rgba([106, 190, 48, 255]), Rgba([143, 86, 59, 255]));
+    let background = |x : u32, y : u32| if (x, y) == (0, 0) { black } else if x < 4 { grass } else { dirt };
+    for (x, y, px) in frames[0].0.enumerate_pixels()
+    {
+        let edge = [(2, 1), (3, 1), (3, 2), (4, 2)].contains(&(x, y));
+        assert_eq!(*px, if edge { green } else { background(x, y) }, "frame 1 at {},{}", x, y);
+    }
+    for (x, y, px) in frames[1].0.enumerate_pixels()
+    {
+        assert_eq!(*px, if (x, y) == (0, 0) { dirt } else { background(x, y) }, "frame 2 at {},{}", x, y);
+    }
+    
+    let ase = Aseprite::read(&data).unwrap();
+    let edges = ase.render(&Options { layer : Some("edges".to_string()), tag : Some("idle".to_string()), ..Options::default() }).unwrap();
+    assert_eq!(edges.len(), 2);
+    assert_eq!(edges[0].0.get_pixel(4, 1)[3], 0);
+    assert_eq!(*edges[0].0.get_pixel(2, 1), green);
+}
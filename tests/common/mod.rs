@@ -0,0 +1,15 @@
+// Helpers shared by the integration tests. Not every test file uses all of them.
+#![allow(dead_code)]
+
+use image::RgbaImage;
+
+// The path of a file in examples/.
+pub fn example(name : &str) -> String
+{
+    format!("{}/examples/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+pub fn example_image(name : &str) -> RgbaImage
+{
+    image::open(example(name)).unwrap().to_rgba()
+}
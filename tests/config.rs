@@ -0,0 +1,120 @@
+use std::path::Path;
+
+use image::GenericImageView;
+
+use autotyler::job;
+use autotyler::job::Edges;
+
+mod common;
+
+#[test]
+fn config_files()
+{
+    let dir = format!("{}/config", env!("CARGO_TARGET_TMPDIR"));
+    std::fs::create_dir_all(&dir).unwrap();
+    let load = |name : &str, text : &str|
+    {
+        let filename = format!("{}/{}", dir, name);
+        std::fs::write(&filename, text).unwrap();
+        job::load_config(&filename)
+    };
+    let in_dir = |filename : &str| Path::new(&dir).join(filename).to_string_lossy().to_string();
+    
+    // top level options are defaults for every job, which can override them
+    let config = load("jobs.toml", r#"
+mode = "3x3"
+size = 16
+edges = "8,6,8,10"
+tiles = ["0,0"]
+
+[[job]]
+input = "grass.png"
+output = "out/grass.png"
+
+[[job]]
+name = "water"
+input = "../water.png"
+output = "sheet.png"
+mode = "basic"
+tiles = ["1,0", "0,0"]
+"#).unwrap();
+    assert_eq!(config.jobs.len(), 2);
+    let (grass, water) = (&config.jobs[0], &config.jobs[1]);
+    assert_eq!((grass.mode.as_str(), grass.size), ("3x3", Some(16)));
+    assert!(grass.edges == Edges::Measures(vec!(8, 6, 8, 10)));
+    assert_eq!(grass.origin_tile_list, vec!((0, 0)));
+    assert_eq!((water.mode.as_str(), water.size), ("basic", Some(16)));
+    assert!(water.edges == grass.edges);
+    assert_eq!(water.origin_tile_list, vec!((1, 0), (0, 0)));
+    // paths are relative to the config file, and jobs are named after their output unless given a name
+    assert_eq!(grass.in_filename, in_dir("grass.png"));
+    assert_eq!(grass.out_filename, in_dir("out/grass.png"));
+    assert_eq!(water.in_filename, in_dir("../water.png"));
+    assert_eq!((grass.name.as_str(), water.name.as_str()), ("grass", "water"));
+    assert_eq!(config.atlas_filename, "");
+    
+    // options that depend on each other work in any order
+    let in_filename = common::example("basic.png");
+    let settings = |text : &str|
+    {
+        let config = load("order.toml", &format!("input = \"{}\"\noutput = \"order.png\"\n{}", in_filename, text)).unwrap();
+        let in_img = image::open(&in_filename).unwrap().to_rgba();
+        config.jobs[0].settings(&in_img).unwrap()
+    };
+    let (a, b) = (settings("edges = \"6\"\nsize = 16\n"), settings("size = 16\nedges = \"6\"\n"));
+    assert_eq!((a.mode, a.size, a.edges), (b.mode, b.size, b.edges));
+    assert_eq!(a.edges, (8, 6, 8, 10));
+    
+    assert_eq!(load("unknown.toml", "input = \"a.png\"\noutput = \"b.png\"\ncolour = \"red\"\n").err(), Some("unsupported option colour".to_string()));
+    assert_eq!(load("unknown_in_job.toml", "[[job]]\ninput = \"a.png\"\noutput = \"b.png\"\nszie = 16\n").err(), Some("unsupported option szie".to_string()));
+    assert_eq!(load("no_output.toml", "[[job]]\ninput = \"a.png\"\n").err(), Some(format!("job 1 in {}/no_output.toml needs an input and an output", dir)));
+}
+
+#[test]
+fn atlas_from_config()
+{
+    let dir = format!("{}/atlas", env!("CARGO_TARGET_TMPDIR"));
+    std::fs::create_dir_all(&dir).unwrap();
+    let filename = format!("{}/atlas.toml", dir);
+    // two jobs on the same input, spread apart differently
+    std::fs::write(&filename, format!(r#"
+atlas = "atlas.png"
+input = "{}"
+mode = "3x3"
+margin = 2
+
+[[job]]
+output = "plain.png"
+padding = 1
+
+[[job]]
+output = "edges.png"
+edges = "8,6,8,10"
+padding = 3
+"#, common::example("3x3.png"))).unwrap();
+    job::run_config(&job::load_config(&filename).unwrap()).unwrap();
+    
+    let atlas = image::open(format!("{}/atlas.png", dir)).unwrap().to_rgba();
+    let manifest = std::fs::read_to_string(format!("{}/atlas.json", dir)).unwrap();
+    let field = |line : &str, key : &str| line.split(&format!("\"{}\": ", key)).nth(1).unwrap().split(&[',', '}'][..]).next().unwrap().to_string();
+    let entries = manifest.lines().filter(|line| line.contains("\"tile_size\"")).collect::<Vec<_>>();
+    assert_eq!(entries.len(), 2);
+    let mut rects = Vec::new();
+    for (entry, (name, padding)) in entries.iter().zip(&[("plain", 1), ("edges", 3)])
+    {
+        let number = |key : &str| field(entry, key).parse::<u32>().unwrap();
+        assert_eq!(field(entry, "name"), format!("\"{}\"", name));
+        let (x, y, width, height) = (number("x"), number("y"), number("width"), number("height"));
+        let (tile_size, margin, separation) = (number("tile_size"), number("margin"), number("separation"));
+        assert_eq!((tile_size, margin, separation), (16, 2 + padding, 2*padding));
+        // margin pixels around 12x4 cells, separation pixels apart
+        assert_eq!((width, height), (2*margin + 12*tile_size + 11*separation, 2*margin + 4*tile_size + 3*separation));
+        assert!(x + width <= atlas.width() && y + height <= atlas.height());
+        // and the same pixels as the job's own output
+        let output = image::open(format!("{}/{}.png", dir, name)).unwrap().to_rgba();
+        assert!(atlas.view(x, y, width, height).to_image() == output, "{}", name);
+        rects.push((x, y, width, height));
+    }
+    let (a, b) = (rects[0], rects[1]);
+    assert!(a.0 + a.2 <= b.0 || b.0 + b.2 <= a.0 || a.1 + a.3 <= b.1 || b.1 + b.3 <= a.1, "{:?} and {:?} overlap", a, b);
+}
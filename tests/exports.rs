@@ -0,0 +1,78 @@
+use image::GenericImageView;
+
+use autotyler::job;
+
+mod common;
+
+#[test]
+fn cells_as_separate_files()
+{
+    let dir = format!("{}/cells", env!("CARGO_TARGET_TMPDIR"));
+    let _ = std::fs::remove_dir_all(&dir);
+    let args = vec!(common::example("basic.png"), format!("{}/grass.png", env!("CARGO_TARGET_TMPDIR")), "cells=".to_string() + &dir, "cell_names=directions".to_string());
+    let config = job::config_from_args(&args).unwrap();
+    job::run_config(&config).unwrap();
+    
+    // 47 cells, the blank one left out, each a copy of its cell in the sheet
+    let expected = common::example_image("basic_output.png");
+    let pngs = std::fs::read_dir(&dir).unwrap().filter(|entry| entry.as_ref().unwrap().file_name().to_string_lossy().starts_with("grass_")).count();
+    assert_eq!(pngs, 47);
+    for &(cell, name) in &[((9, 2), "grass_N_NE_E_SE_S_SW_W_NW.png"), ((0, 3), "grass_none.png"), ((1, 0), "grass_E_S.png")]
+    {
+        let img = image::open(format!("{}/{}", dir, name)).unwrap().to_rgba();
+        assert!(img == expected.view(cell.0*16, cell.1*16, 16, 16).to_image(), "{}", name);
+    }
+    let manifest = std::fs::read_to_string(format!("{}/grass.json", dir)).unwrap();
+    assert_eq!(manifest.matches("\"file\"").count(), 47);
+    assert!(manifest.contains("{\"file\": \"grass_none.png\", \"x\": 0, \"y\": 3, \"mask\": 16, \"probability\": 1}"));
+}
+
+#[test]
+fn unity_rule_tile()
+{
+    let out_filename = format!("{}/unity.png", env!("CARGO_TARGET_TMPDIR"));
+    let asset_filename = format!("{}/unity.asset", env!("CARGO_TARGET_TMPDIR"));
+    let _ = std::fs::remove_file(format!("{}.meta", out_filename));
+    let args = vec!(common::example("basic.png"), out_filename.clone(), format!("unity={}", asset_filename));
+    let config = job::config_from_args(&args).unwrap();
+    job::run_config(&config).unwrap();
+    
+    let asset = std::fs::read_to_string(&asset_filename).unwrap();
+    let meta = std::fs::read_to_string(format!("{}.meta", out_filename)).unwrap();
+    assert_eq!(asset.matches("  - m_Id: ").count(), 47);
+    assert_eq!(meta.matches("      name: unity_").count(), 47);
+    // every sprite a rule uses is sliced out of the image, and the cells are measured from the bottom
+    let guid = meta.lines().find_map(|line| line.strip_prefix("guid: ")).unwrap();
+    for line in asset.lines().filter(|line| line.starts_with("    - {fileID: "))
+    {
+        let id = line.split("fileID: ").nth(1).unwrap().split(',').next().unwrap();
+        assert!(meta.contains(&format!("      internalID: {}\n", id)), "{}", line);
+        assert!(line.contains(guid));
+    }
+    assert!(meta.contains("      name: unity_0_0\n      rect:\n        serializedVersion: 2\n        x: 0\n        y: 48\n"));
+    // surrounded on all sides, and surrounded by nothing with the corners not mattering
+    assert!(asset.contains(&format!("    m_Neighbors: {}\n", "01000000".repeat(8))));
+    assert!(asset.contains("    m_Neighbors: 0000000002000000000000000200000002000000000000000200000000000000\n"));
+    
+    // regenerating keeps the GUID of the existing .meta
+    std::fs::write(format!("{}.meta", out_filename), meta.replace(guid, "0123456789abcdef0123456789abcdef")).unwrap();
+    job::run_config(&config).unwrap();
+    assert!(std::fs::read_to_string(&asset_filename).unwrap().contains("guid: 0123456789abcdef0123456789abcdef, type: 3}"));
+}
+
+#[test]
+fn ldtk_rules()
+{
+    let out_filename = format!("{}/ldtk.png", env!("CARGO_TARGET_TMPDIR"));
+    let ldtk_filename = format!("{}/ldtk.json", env!("CARGO_TARGET_TMPDIR"));
+    let args = vec!(common::example("basic.png"), out_filename, format!("ldtk={}", ldtk_filename), "ldtk_value=2".to_string(), "padding=1".to_string());
+    let config = job::config_from_args(&args).unwrap();
+    job::run_config(&config).unwrap();
+    
+    let snippet = std::fs::read_to_string(&ldtk_filename).unwrap();
+    assert!(snippet.contains("\"relPath\": \"ldtk.png\", \"pxWid\": 216, \"pxHei\": 72, \"tileGridSize\": 16, \"spacing\": 2, \"padding\": 1,"));
+    assert_eq!(snippet.matches("\"pattern\": ").count(), 47);
+    // the fully surrounded cell is at 9,2 and the lone one at 0,3
+    assert!(snippet.contains("\"tileIds\": [33], \"tileRectsIds\": [[33]], \"alpha\": 1, \"chance\": 1, \"breakOnMatch\": true,\n          \"pattern\": [2, 2, 2, 2, 2, 2, 2, 2, 2],"));
+    assert!(snippet.contains("\"tileIds\": [36], \"tileRectsIds\": [[36]], \"alpha\": 1, \"chance\": 1, \"breakOnMatch\": true,\n          \"pattern\": [0, -2, 0, -2, 2, -2, 0, -2, 0],"));
+}
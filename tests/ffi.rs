@@ -0,0 +1,93 @@
+use std::ffi::CStr;
+use std::ffi::CString;
+
+use image::RgbaImage;
+
+use autotyler::capi;
+use autotyler::wasm;
+
+mod common;
+
+#[test]
+fn c_abi_buffers()
+{
+    let in_img = common::example_image("3x3.png");
+    let expected = common::example_image("3x3_output_edges_8,6,8,10.png");
+    let options = |mode : u32, edges : [u32; 4], border : u32, tiles : &[u32]| wasm::AssembleOptions { mode, size : 16, edges, border, offset_x : 0, offset_y : 0, tile_count : tiles.len() as u32/2, tiles : tiles.as_ptr() };
+    let run_with = |options : *const wasm::AssembleOptions, rgba : *const u8|
+    {
+        unsafe
+        {
+            let result = wasm::autotyler_assemble(options, rgba, in_img.width(), in_img.height());
+            let ret = if (*result).error.is_null()
+            {
+                let pixels = std::slice::from_raw_parts((*result).pixels, ((*result).width*(*result).height*4) as usize).to_vec();
+                Ok(RgbaImage::from_raw((*result).width, (*result).height, pixels).unwrap())
+            }
+            else
+            {
+                Err(String::from_utf8(std::slice::from_raw_parts((*result).error, (*result).error_len as usize).to_vec()).unwrap())
+            };
+            wasm::autotyler_free_result(result);
+            ret
+        }
+    };
+    let run = |mode : u32, edges : [u32; 4], border : u32, tiles : &[u32]| run_with(&options(mode, edges, border, tiles), in_img.as_ptr());
+    assert!(run(2, [8, 6, 8, 10], 2, &[0, 0]).unwrap() == expected);
+    assert_eq!(run(2, [8, 6, 8, 10], 2, &[1, 0]).unwrap_err(), "the tile list points outside of the input image");
+    assert_eq!(run(2, [20, 8, 8, 8], 2, &[0, 0]).unwrap_err(), "edges can't be more than the tile size of 16");
+    assert_eq!(run(1, [0, 0, 0, 0], 40, &[0, 0, 1, 0]).unwrap_err(), "border can't be more than the tile size of 16");
+    assert!(run(99, [8, 6, 8, 10], 2, &[]).is_err());
+    assert_eq!(run_with(&options(2, [8, 6, 8, 10], 2, &[0, 0]), std::ptr::null()).unwrap_err(), "got a null pixel buffer");
+    assert_eq!(run_with(std::ptr::null(), in_img.as_ptr()).unwrap_err(), "got null options");
+}
+
+#[test]
+fn c_api()
+{
+    let in_img = common::example_image("3x3.png");
+    let expected = common::example_image("3x3_output_edges_8,6,8,10.png");
+    let text = |text : &str| CString::new(text).unwrap();
+    unsafe
+    {
+        let handle = capi::autotyler_job_new();
+        assert_eq!(capi::autotyler_job_set_option(handle, text("mode").as_ptr(), text("3x3").as_ptr()), 0);
+        assert_eq!(capi::autotyler_job_set_option(handle, text("edges").as_ptr(), text("8,6,8,10").as_ptr()), 0);
+        assert_eq!(capi::autotyler_job_set_option(handle, text("size").as_ptr(), text("big").as_ptr()), -1);
+        assert_eq!(CStr::from_ptr(capi::autotyler_job_error(handle)).to_str().unwrap(), "expected a number, got 'big'");
+        assert_eq!(capi::autotyler_job_run(handle), -1);
+        
+        assert_eq!(capi::autotyler_job_set_input(handle, in_img.as_ptr(), in_img.width(), in_img.height()), 0);
+        assert_eq!(capi::autotyler_job_run(handle), 0);
+        assert!(capi::autotyler_job_error(handle).is_null());
+        let (mut width, mut height) = (0, 0);
+        let pixels = capi::autotyler_job_output(handle, &mut width, &mut height);
+        let output = RgbaImage::from_raw(width, height, std::slice::from_raw_parts(pixels, (width*height*4) as usize).to_vec()).unwrap();
+        assert!(output == expected);
+        
+        // what would make the assembly panic is an error instead
+        let tile = RgbaImage::new(16, 16);
+        assert_eq!(capi::autotyler_job_set_input(handle, tile.as_ptr(), 16, 16), 0);
+        assert_eq!(capi::autotyler_job_run(handle), -1);
+        assert_eq!(CStr::from_ptr(capi::autotyler_job_error(handle)).to_str().unwrap(), "the tile list points outside of the input image");
+        assert!(capi::autotyler_job_output(handle, &mut width, &mut height).is_null());
+        assert_eq!(capi::autotyler_job_set_input(handle, in_img.as_ptr(), in_img.width(), in_img.height()), 0);
+        assert_eq!(capi::autotyler_job_set_option(handle, text("edges").as_ptr(), text("20,8,8,8").as_ptr()), 0);
+        assert_eq!(capi::autotyler_job_run(handle), -1);
+        assert_eq!(CStr::from_ptr(capi::autotyler_job_error(handle)).to_str().unwrap(), "edges can't be more than the tile size of 16");
+        capi::autotyler_job_free(handle);
+    }
+}
+
+#[test]
+fn c_header()
+{
+    // include/autotyler.h is what cbindgen makes out of the current source, so it declares everything the library exports as it is
+    let dir = env!("CARGO_MANIFEST_DIR");
+    let config = cbindgen::Config::from_file(format!("{}/cbindgen.toml", dir)).unwrap();
+    let mut generated = Vec::new();
+    cbindgen::Builder::new().with_config(config).with_crate(dir).generate().unwrap().write(&mut generated);
+    let header = std::fs::read_to_string(format!("{}/include/autotyler.h", dir)).unwrap();
+    assert!(String::from_utf8(generated).unwrap() == header, "include/autotyler.h is out of date, run: cbindgen --config cbindgen.toml --output include/autotyler.h");
+    assert!(header.contains(&format!("#define AUTOTYLER_API_VERSION {}\n", capi::API_VERSION)));
+}
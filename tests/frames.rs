@@ -0,0 +1,55 @@
+use std::collections::HashMap;
+
+use image::RgbaImage;
+
+use autotyler::assemble;
+use autotyler::assemble::Settings;
+use autotyler::frames;
+use autotyler::job::Job;
+
+mod common;
+
+#[test]
+fn frames()
+{
+    let first = common::example_image("minitiles.png");
+    let second = common::example_image("minitiles2.png");
+    let settings = Settings { mode : "minitiles".to_string(), size : 16, edges : (8, 8, 8, 8), border : 2, origin_tile_list : vec!((0, 0), (1, 0), (2, 0), (3, 0), (4, 0)) };
+    
+    // two frames on top of each other turn into two sheets side by side
+    let input = RgbaImage::from_fn(80, 32, |x, y| if y < 16 { *first.get_pixel(x, y) } else { *second.get_pixel(x, y - 16) });
+    let in_frames = frames::split(&input, 2, true).unwrap();
+    let sheets = in_frames.iter().map(|frame| assemble::assemble(frame, &settings)).collect::<Vec<_>>();
+    let strip = frames::strip(&sheets);
+    let expected = [common::example_image("minitiles_output.png"), common::example_image("minitiles2_output.png")];
+    assert!(strip == frames::strip(&expected));
+    assert!(frames::split(&input, 3, true).is_err());
+    assert_eq!(Job::default().set_option("frames", "0"), Err("frames must be at least 1".to_string()));
+    
+    // animated GIFs keep their frames and timing
+    let flat = |colour : [u8; 4]| RgbaImage::from_pixel(8, 8, image::Rgba(colour));
+    let filename = format!("{}/frames.gif", env!("CARGO_TARGET_TMPDIR"));
+    frames::save(&[flat([255, 0, 0, 255]), flat([0, 0, 255, 255]), flat([0, 0, 0, 0])], 250, None, &filename).unwrap();
+    let (loaded, delay) = frames::load(&filename, &Default::default()).unwrap();
+    assert_eq!(delay, Some(250));
+    assert_eq!(loaded.len(), 3);
+    assert_eq!(*loaded[1].get_pixel(4, 4), image::Rgba([0, 0, 255, 255]));
+    assert_eq!(loaded[2].get_pixel(4, 4)[3], 0);
+    
+    // and every frame of a GIF is assembled when the job runs on the file, like the Python bindings do
+    let filename = format!("{}/minitiles.gif", env!("CARGO_TARGET_TMPDIR"));
+    let inputs = [common::example_image("minitiles.png"), common::example_image("minitiles2.png")];
+    frames::save(&inputs, 100, None, &filename).unwrap();
+    let mut job = Job { mode : "minitiles".to_string(), size : Some(16), ..Job::default() };
+    for x in 0..5
+    {
+        job.add_tile(&format!("{},0", x)).unwrap();
+    }
+    let output = job.run_file(&filename, &HashMap::new()).unwrap();
+    // the GIF has its colours quantized, so compare with its own frames
+    let (gif_frames, _) = frames::load(&filename, &Default::default()).unwrap();
+    let expected = frames::strip(&gif_frames.iter().map(|frame| job.run_image(frame, &HashMap::new()).unwrap()).collect::<Vec<_>>());
+    assert_eq!(output.dimensions(), (2*12*16, 4*16));
+    assert!(output == expected);
+    assert!(job.run_file(&common::example("minitiles.png"), &HashMap::new()).unwrap() == job.run_image(&inputs[0], &HashMap::new()).unwrap());
+}
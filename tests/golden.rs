@@ -0,0 +1,130 @@
+use image::RgbaImage;
+
+use autotyler::assemble;
+use autotyler::job::Job;
+use autotyler::masks;
+use autotyler::verify;
+
+mod common;
+
+// Runs autotyler with the given command line and compares the result pixel for pixel with a committed output.
+// On a mismatch, a diff image (mismatching pixels in red over a faded copy of the expected output) is written next to the test binaries.
+fn check(name : &str, args : &[&str], expected : &str)
+{
+    let mut args = args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+    args[0] = common::example(&args[0]);
+    args.insert(1, "unused.png".to_string());
+    
+    let job = Job::from_args(&args).unwrap();
+    let in_img = image::open(&job.in_filename).unwrap().to_rgba();
+    let settings = job.settings(&in_img).unwrap();
+    let out_img = assemble::assemble(&in_img, &settings);
+    
+    let expected_img = common::example_image(expected);
+    assert_eq!(out_img.dimensions(), expected_img.dimensions(), "{}: output has the wrong dimensions", name);
+    
+    let mut diff = RgbaImage::new(out_img.width(), out_img.height());
+    let mut mismatches = 0;
+    for (x, y, expected_px) in expected_img.enumerate_pixels()
+    {
+        if out_img.get_pixel(x, y) != expected_px
+        {
+            mismatches += 1;
+            diff.put_pixel(x, y, image::Rgba([255, 0, 0, 255]));
+        }
+        else
+        {
+            let gray = ((expected_px[0] as u32 + expected_px[1] as u32 + expected_px[2] as u32)/3) as u8;
+            diff.put_pixel(x, y, image::Rgba([gray, gray, gray, expected_px[3]/4]));
+        }
+    }
+    if mismatches > 0
+    {
+        let diff_filename = format!("{}/{}_diff.png", env!("CARGO_TARGET_TMPDIR"), name);
+        diff.save(&diff_filename).unwrap();
+        panic!("{}: {} pixels differ from {}, see {}", name, mismatches, expected, diff_filename);
+    }
+}
+
+#[test]
+fn basic()
+{
+    check("basic", &["basic.png"], "basic_output.png");
+    check("basic2", &["basicborder.png"], "basic2_output.png");
+}
+
+#[test]
+fn basic_border()
+{
+    check("basic_border", &["basicborder.png", "mode=basic_border"], "basicborder_output.png");
+    check("basic_border_4", &["basicborder.png", "mode=basic_border", "border=4"], "basicborder_output_border_4.png");
+}
+
+#[test]
+fn three_by_three()
+{
+    check("3x3", &["3x3.png", "mode=3x3"], "3x3_output.png");
+    check("3x3_edges", &["3x3.png", "mode=3x3", "edges=8,6,8,10"], "3x3_output_edges_8,6,8,10.png");
+}
+
+#[test]
+fn three_by_three_plus()
+{
+    check("3x3plus", &["3x3plus.png", "mode=3x3plus"], "3x3plus_output.png");
+    check("3x3plus_edges", &["3x3plus.png", "mode=3x3plus", "edges=8,6,8,10"], "3x3plus_output_edges_8,6,8,10.png");
+}
+
+#[test]
+fn four_by_four()
+{
+    check("4x4", &["4x4.png", "mode=4x4"], "4x4_output.png");
+    check("4x4plus", &["4x4plus.png", "mode=4x4plus"], "4x4plus_output.png");
+}
+
+#[test]
+fn minitiles()
+{
+    check("minitiles", &["minitiles.png", "mode=minitiles"], "minitiles_output.png");
+    check("minitiles2", &["minitiles2.png", "mode=minitiles"], "minitiles2_output.png");
+}
+
+// The outlines of the examples are thinner than their corners, so edges=auto has to fall back to the edges they were made with.
+#[test]
+fn auto_edges()
+{
+    for &(input, mode, expected) in &[("basic.png", "basic", "basic_output.png"), ("3x3.png", "3x3", "3x3_output.png"), ("4x4.png", "4x4", "4x4_output.png"), ("minitiles2.png", "minitiles", "minitiles2_output.png")]
+    {
+        check(&format!("{}_auto", mode), &[input, &format!("mode={}", mode), "edges=auto"], expected);
+    }
+}
+
+// Every committed output passes --verify with the edges it was made with.
+#[test]
+fn outputs_verify()
+{
+    let mut names = std::fs::read_dir(common::example("")).unwrap().map(|entry| entry.unwrap().file_name().to_string_lossy().to_string()).filter(|name| name.contains("_output")).collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(names.len(), 12);
+    for name in &names
+    {
+        let edges = if name.contains("edges_8,6,8,10") { (8, 6, 8, 10) } else { (8, 8, 8, 8) };
+        let problems = verify::verify(&common::example_image(name), 16, edges, &masks::GODOT);
+        assert!(problems.is_empty(), "{}:\n{}", name, problems.join("\n"));
+    }
+}
+
+#[test]
+fn tiles_from_other_images()
+{
+    let mut args = vec!(common::example("basic.png"), "unused.png".to_string(), "mode=minitiles".to_string(), "offset=5,5".to_string());
+    args.extend((0..5).map(|x| format!("{}:{},0", common::example("minitiles2.png"), x)));
+    let job = Job::from_args(&args).unwrap();
+    assert_eq!(job.tile_sources(), vec!(common::example("minitiles2.png")));
+    
+    let in_img = image::open(&job.in_filename).unwrap().to_rgba();
+    let sources = job.tile_sources().into_iter().map(|filename| (filename.clone(), image::open(&filename).unwrap().to_rgba())).collect();
+    let (job, combined) = job.with_tile_sources(&in_img, &sources).unwrap();
+    let settings = job.settings(&combined).unwrap();
+    let expected = common::example_image("minitiles2_output.png");
+    assert!(assemble::assemble(&combined, &settings) == expected);
+}
@@ -0,0 +1,639 @@
+use image::Rgba;
+use image::RgbaImage;
+
+use autotyler::assemble;
+use autotyler::assemble::Settings;
+use autotyler::bitmask;
+use autotyler::check;
+use autotyler::detect;
+use autotyler::job::Job;
+use autotyler::masks::*;
+use autotyler::padding;
+use autotyler::preview;
+use autotyler::scale;
+use autotyler::variants;
+use autotyler::verify;
+
+mod common;
+
+// xorshift, so the inputs are random but the same on every run
+struct Rng(u64);
+
+impl Rng
+{
+    fn next(&mut self) -> u32
+    {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 32) as u32
+    }
+    fn range(&mut self, min : u32, max : u32) -> u32
+    {
+        min + self.next() % (max - min + 1)
+    }
+}
+
+// What one quarter of a tile looks like, depending on its neighbours.
+#[derive(Clone, Copy, PartialEq)]
+enum Look
+{
+    OuterCorner,
+    HorizontalEdge,
+    VerticalEdge,
+    InnerCorner,
+    Fill,
+}
+
+// A random but seamless tileset: every quarter of every tile is cut out of one of a few random textures,
+// picked by the look of the quarter. Edges are the same texture everywhere along a side, so any tile
+// built from these fits next to any other tile with matching neighbours.
+struct Tileset
+{
+    size : u32,
+    left : u32,
+    top : u32,
+    // indexed by quarter (top left, top right, bottom left, bottom right)
+    outer_corners : Vec<RgbaImage>,
+    inner_corners : Vec<RgbaImage>,
+    // indexed by side (left, top, right, bottom)
+    edges : Vec<RgbaImage>,
+    fill : RgbaImage,
+}
+
+impl Tileset
+{
+    fn new(rng : &mut Rng, size : u32, left : u32, top : u32) -> Tileset
+    {
+        let mut texture = || RgbaImage::from_fn(size, size, |_, _| Rgba([rng.next() as u8, rng.next() as u8, rng.next() as u8, rng.next() as u8]));
+        Tileset
+        {
+            size,
+            left,
+            top,
+            outer_corners : (0..4).map(|_| texture()).collect(),
+            inner_corners : (0..4).map(|_| texture()).collect(),
+            edges : (0..4).map(|_| texture()).collect(),
+            fill : texture(),
+        }
+    }
+    
+    // The pixel a tile with the given bitmask has at (x, y), with the looks a mode can't produce replaced by fill.
+    fn pixel(&self, mask : u16, x : u32, y : u32, available : &[Look]) -> Rgba<u8>
+    {
+        let right_half = x >= self.left;
+        let bottom_half = y >= self.top;
+        let (horizontal, vertical, corner) = match (right_half, bottom_half)
+        {
+            (false, false) => (LEFT, TOP, TOP_LEFT),
+            (true, false) => (RIGHT, TOP, TOP_RIGHT),
+            (false, true) => (LEFT, BOTTOM, BOTTOM_LEFT),
+            (true, true) => (RIGHT, BOTTOM, BOTTOM_RIGHT),
+        };
+        let quarter = right_half as usize + 2*bottom_half as usize;
+        let look = match (mask & horizontal != 0, mask & vertical != 0, mask & corner != 0)
+        {
+            (false, false, _) => Look::OuterCorner,
+            (true, false, _) => Look::HorizontalEdge,
+            (false, true, _) => Look::VerticalEdge,
+            (true, true, false) => Look::InnerCorner,
+            (true, true, true) => Look::Fill,
+        };
+        let look = if available.contains(&look) { look } else { Look::Fill };
+        let texture = match look
+        {
+            Look::OuterCorner => &self.outer_corners[quarter],
+            Look::InnerCorner => &self.inner_corners[quarter],
+            Look::HorizontalEdge => &self.edges[if bottom_half { 3 } else { 1 }],
+            Look::VerticalEdge => &self.edges[if right_half { 2 } else { 0 }],
+            Look::Fill => &self.fill,
+        };
+        *texture.get_pixel(x, y)
+    }
+    
+    // An input image with a tile for each bitmask, laid out as given.
+    fn input(&self, layout : &[&[u16]]) -> RgbaImage
+    {
+        let size = self.size;
+        let all = [Look::OuterCorner, Look::HorizontalEdge, Look::VerticalEdge, Look::InnerCorner, Look::Fill];
+        RgbaImage::from_fn(layout[0].len() as u32*size, layout.len() as u32*size, |x, y|
+        {
+            let mask = layout[(y/size) as usize][(x/size) as usize];
+            if mask == 0 { Rgba([0, 0, 0, 0]) } else { self.pixel(mask, x%size, y%size, &all) }
+        })
+    }
+}
+
+const NO_INNER : &[Look] = &[Look::OuterCorner, Look::HorizontalEdge, Look::VerticalEdge, Look::Fill];
+const ALL : &[Look] = &[Look::OuterCorner, Look::HorizontalEdge, Look::VerticalEdge, Look::InnerCorner, Look::Fill];
+
+// mode, bitmasks of the input tiles, looks the mode can produce
+type ModeLayout = (&'static str, &'static [&'static [u16]], &'static [Look]);
+
+const MODES : &[ModeLayout] = &[
+    ("basic", &[&[0x1ff, 0x010]], &[Look::OuterCorner, Look::Fill]),
+    ("3x3", &[&[0x1b0, 0x1f8, 0x0d8], &[0x1b6, 0x1ff, 0x0db], &[0x036, 0x03f, 0x01b]], NO_INNER),
+    ("3x3plus", &[&[0x1b0, 0x1f8, 0x0d8, 0x0ba], &[0x1b6, 0x1ff, 0x0db, 0], &[0x036, 0x03f, 0x01b, 0]], ALL),
+    ("4x4", &[&[0x090, 0x1b0, 0x1f8, 0x0d8], &[0x092, 0x1b6, 0x1ff, 0x0db], &[0x012, 0x036, 0x03f, 0x01b], &[0x010, 0x030, 0x038, 0x018]], NO_INNER),
+    ("4x4plus", &[&[0x090, 0x1b0, 0x1f8, 0x0d8, 0x0ba], &[0x092, 0x1b6, 0x1ff, 0x0db, 0], &[0x012, 0x036, 0x03f, 0x01b, 0], &[0x010, 0x030, 0x038, 0x018, 0]], ALL),
+    ("minitiles", &[&[0x010, 0x092, 0x038, 0x0ba, 0x1ff]], ALL),
+];
+
+fn default_tiles(mode : &str) -> Vec<(u32, u32)>
+{
+    match mode
+    {
+        "basic" | "basic_border" => vec!((0, 0), (1, 0)),
+        "3x3plus" => vec!((0, 0), (3, 0)),
+        "4x4plus" => vec!((0, 0), (4, 0)),
+        "minitiles" => vec!((0, 0), (1, 0), (2, 0), (3, 0), (4, 0)),
+        _ => vec!((0, 0)),
+    }
+}
+
+// Every cell of the output must be exactly the tile with that cell's bitmask, as far as the mode can tell,
+// and the output must pass verify().
+#[test]
+fn seamless_inputs_give_consistent_sheets()
+{
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+    for &(mode, layout, available) in MODES
+    {
+        for _ in 0..16
+        {
+            let size = rng.range(2, 12);
+            let left = rng.range(1, size-1);
+            let top = rng.range(1, size-1);
+            let tileset = Tileset::new(&mut rng, size, left, top);
+            let settings = Settings
+            {
+                mode : mode.to_string(),
+                size,
+                edges : (left, top, size-left, size-top),
+                border : 2,
+                origin_tile_list : default_tiles(mode),
+            };
+            let out_img = assemble::assemble(&tileset.input(layout), &settings);
+            
+            let problems = verify::verify(&out_img, size, settings.edges, &GODOT);
+            assert!(problems.is_empty(), "mode {} size {} edges {:?}:\n{}", mode, size, settings.edges, problems.join("\n"));
+            
+            for (x, y, px) in out_img.enumerate_pixels()
+            {
+                let mask = godot_mask((x/size, y/size));
+                let expected = if mask == 0 { Rgba([0, 0, 0, 0]) } else { tileset.pixel(mask, x%size, y%size, available) };
+                assert_eq!(*px, expected, "mode {} size {} edges {:?}: wrong pixel at ({}, {}) in cell ({}, {})", mode, size, settings.edges, x, y, x/size, y/size);
+            }
+        }
+    }
+}
+
+// basic_border makes its edges up out of the corners, so it gets noise instead of a seamless tileset.
+#[test]
+fn basic_border_gives_consistent_sheets()
+{
+    let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+    for _ in 0..16
+    {
+        let size = rng.range(2, 12);
+        let left = rng.range(1, size-1);
+        let top = rng.range(1, size-1);
+        let settings = Settings
+        {
+            mode : "basic_border".to_string(),
+            size,
+            edges : (left, top, size-left, size-top),
+            border : rng.range(0, size/2),
+            origin_tile_list : default_tiles("basic_border"),
+        };
+        let in_img = RgbaImage::from_fn(2*size, size, |_, _| Rgba([rng.next() as u8, rng.next() as u8, rng.next() as u8, rng.next() as u8]));
+        let problems = verify::verify(&assemble::assemble(&in_img, &settings), size, settings.edges, &GODOT);
+        assert!(problems.is_empty(), "size {} edges {:?} border {}:\n{}", size, settings.edges, settings.border, problems.join("\n"));
+    }
+}
+
+#[test]
+fn broken_sheets_are_caught()
+{
+    let img = common::example_image("minitiles2_output.png");
+    assert!(verify::verify(&img, 16, (8, 8, 8, 8), &GODOT).is_empty());
+    
+    let mut blank = img.clone();
+    blank.put_pixel(10*16 + 3, 16 + 5, Rgba([255, 0, 0, 255]));
+    assert!(verify::verify(&blank, 16, (8, 8, 8, 8), &GODOT).iter().any(|problem| problem.contains("blank cell")));
+    
+    let mut seam = img.clone();
+    let px = *seam.get_pixel(5*16, 16 + 8);
+    seam.put_pixel(5*16, 16 + 8, Rgba([px[0] ^ 0xff, px[1], px[2], 255]));
+    assert!(!verify::verify(&seam, 16, (8, 8, 8, 8), &GODOT).is_empty());
+    
+    assert!(!verify::verify(&img, 32, (8, 8, 8, 8), &GODOT).is_empty());
+    
+    // the other layouts hold the same cells, and aren't godot sheets
+    for layout in &[&BLOB, &BLOB_PACKED]
+    {
+        let (rearranged, _) = variants::rearrange(&img, 16, &[], layout);
+        assert!(verify::verify(&rearranged, 16, (8, 8, 8, 8), layout).is_empty(), "{}", layout.name);
+        assert!(!verify::verify(&rearranged, 16, (8, 8, 8, 8), &GODOT).is_empty(), "{}", layout.name);
+        let mut seam = rearranged.clone();
+        let (x, y) = layout.cell(godot_mask((5, 1))).unwrap();
+        let px = *seam.get_pixel(x*16, y*16 + 8);
+        seam.put_pixel(x*16, y*16 + 8, Rgba([px[0] ^ 0xff, px[1], px[2], 255]));
+        assert!(!verify::verify(&seam, 16, (8, 8, 8, 8), layout).is_empty(), "{}", layout.name);
+    }
+}
+
+fn seams_in_example(filename : &str, mode : &str, origin_tile_list : Vec<(u32, u32)>) -> Vec<check::Discontinuity>
+{
+    let in_img = common::example_image(filename);
+    let settings = Settings
+    {
+        mode : mode.to_string(),
+        size : 16,
+        edges : (8, 8, 8, 8),
+        border : 2,
+        origin_tile_list,
+    };
+    check::check(&in_img, &settings).unwrap()
+}
+
+#[test]
+fn seamless_examples_pass_the_seam_check()
+{
+    assert!(seams_in_example("4x4plus.png", "4x4plus", vec!((0, 0), (4, 0))).is_empty());
+    assert!(seams_in_example("minitiles2.png", "minitiles", vec!((0, 0), (1, 0), (2, 0), (3, 0), (4, 0))).is_empty());
+}
+
+#[test]
+fn broken_examples_fail_the_seam_check()
+{
+    // the 3x3 example's edge tiles don't line up with its corners
+    let found = seams_in_example("3x3.png", "3x3", vec!((0, 0)));
+    assert!(!found.is_empty());
+    assert!(found.iter().all(|discontinuity| !discontinuity.pixels.is_empty()));
+}
+
+#[test]
+fn every_neighbourhood_has_a_tile()
+{
+    for bits in 0..256u32
+    {
+        let filled = |i : u32| bits & (1 << i) != 0;
+        let neighbours = [[filled(0), filled(1), filled(2)], [filled(3), true, filled(4)], [filled(5), filled(6), filled(7)]];
+        let mask = minimal_mask(neighbours);
+        assert!(godot_cell(mask).is_some(), "no cell for mask {:#05x}", mask);
+    }
+}
+
+#[test]
+fn previews_use_the_tile_matching_each_neighbourhood()
+{
+    let mut rng = Rng(0x1234_5678_9abc_def0);
+    let (mode, layout, available) = MODES.iter().find(|(mode, _, _)| *mode == "4x4plus").copied().unwrap();
+    let size = 6;
+    let tileset = Tileset::new(&mut rng, size, 3, 2);
+    let settings = Settings
+    {
+        mode : mode.to_string(),
+        size,
+        edges : (3, 2, 3, 4),
+        border : 2,
+        origin_tile_list : default_tiles(mode),
+    };
+    let sheet = assemble::assemble(&tileset.input(layout), &settings);
+    let map = preview::parse_map(preview::DEFAULT_MAP).unwrap();
+    let preview_img = preview::render(&sheet, size, &map, &GODOT, &[]);
+    
+    let filled = |x : i64, y : i64| x >= 0 && y >= 0 && map.get(y as usize).and_then(|row| row.get(x as usize)).copied().unwrap_or(false);
+    for (x, y, px) in preview_img.enumerate_pixels()
+    {
+        let (cx, cy) = ((x/size) as i64, (y/size) as i64);
+        let expected = if !filled(cx, cy)
+        {
+            Rgba([0, 0, 0, 0])
+        }
+        else
+        {
+            let neighbours = [
+                [filled(cx-1, cy-1), filled(cx, cy-1), filled(cx+1, cy-1)],
+                [filled(cx-1, cy), true, filled(cx+1, cy)],
+                [filled(cx-1, cy+1), filled(cx, cy+1), filled(cx+1, cy+1)],
+            ];
+            tileset.pixel(minimal_mask(neighbours), x%size, y%size, available)
+        };
+        assert_eq!(*px, expected, "wrong pixel at ({}, {}) in map cell ({}, {})", x, y, cx, cy);
+    }
+}
+
+#[test]
+fn bitmask_overlays_can_be_read_back()
+{
+    for &size in &[3, 16, 20]
+    {
+        let sheet = RgbaImage::new(12*size, 4*size);
+        let img = bitmask::draw(&sheet, size, &godot_mask);
+        for cy in 0..4
+        {
+            for cx in 0..12
+            {
+                let mut mask = 0;
+                for bit in 0..9
+                {
+                    let (x, y) = (cx*size + (bit % 3 * 2 + 1)*size/6, cy*size + (bit / 3 * 2 + 1)*size/6);
+                    if img.get_pixel(x, y)[3] != 0
+                    {
+                        mask |= 1 << bit;
+                    }
+                }
+                assert_eq!(mask, godot_mask((cx, cy)), "size {} cell ({}, {})", size, cx, cy);
+            }
+        }
+    }
+}
+
+#[test]
+fn variant_cells_come_from_their_alternative_tiles()
+{
+    let mut rng = Rng(0x0bad_5eed_dead_beef);
+    let (mode, layout, _) = MODES.iter().find(|(mode, _, _)| *mode == "minitiles").copied().unwrap();
+    let size = 8;
+    let tileset = Tileset::new(&mut rng, size, 4, 4);
+    let mut in_img = tileset.input(layout);
+    // a sixth tile to use as an alternative fill: the fill tile upside down
+    let mut wide = RgbaImage::new(6*size, size);
+    image::imageops::replace(&mut wide, &in_img, 0, 0);
+    image::imageops::replace(&mut wide, &image::imageops::flip_vertical(&image::imageops::crop(&mut in_img, 4*size, 0, size, size).to_image()), 5*size, 0);
+    
+    let settings = Settings
+    {
+        mode : mode.to_string(),
+        size,
+        edges : (4, 4, 4, 4),
+        border : 2,
+        origin_tile_list : default_tiles(mode),
+    };
+    let mut groups = default_tiles(mode).into_iter().map(|tile| vec!((tile, 1.0))).collect::<Vec<_>>();
+    groups[4].push(((5, 0), 3.0));
+    let (sheet, variants) = variants::assemble_variants(&wide, &settings, &groups);
+    
+    let mut variant_settings = settings.clone();
+    variant_settings.origin_tile_list[4] = (5, 0);
+    let alternative = assemble::assemble(&wide, &variant_settings);
+    
+    let extra = variants.iter().filter(|variant| variant.cell.1 >= 4).collect::<Vec<_>>();
+    assert!(!extra.is_empty());
+    assert_eq!(sheet.height(), (4 + (extra.len() as u32).div_ceil(12))*size);
+    for variant in &extra
+    {
+        let (x, y) = godot_cell(variant.mask).unwrap();
+        for iy in 0..size
+        {
+            for ix in 0..size
+            {
+                assert_eq!(sheet.get_pixel(variant.cell.0*size + ix, variant.cell.1*size + iy), alternative.get_pixel(x*size + ix, y*size + iy));
+            }
+        }
+        assert!((variant.probability - 0.75).abs() < 1e-9);
+    }
+    for variant in &variants
+    {
+        let total : f64 = variants.iter().filter(|other| other.mask == variant.mask).map(|other| other.probability).sum();
+        assert!((total - 1.0).abs() < 1e-9, "chances for mask {:#05x} add up to {}", variant.mask, total);
+    }
+}
+
+#[test]
+fn alternative_cells_follow_the_layout()
+{
+    let in_img = common::example_image("minitiles2.png");
+    let build = |layout : &str|
+    {
+        let mut job = Job { mode : "minitiles".to_string(), layout : arrangement(layout).unwrap(), ..Job::default() };
+        for tile in &["0,0", "1,0", "2,0", "3,0", "4,0/0,0@3"]
+        {
+            job.add_tile(tile).unwrap();
+        }
+        let (sheets, variants, size) = job.build(std::slice::from_ref(&in_img)).unwrap();
+        (sheets[0].height()/size, variants::extra_count(job.layout, &variants), variants.len())
+    };
+    let (godot_rows, godot_extra, godot_total) = build("godot");
+    assert!(godot_extra > 0);
+    // the blob layouts have cells with alternatives in their own last rows, which don't count as extra
+    for layout in &["blob", "blob_packed"]
+    {
+        let (rows, extra, total) = build(layout);
+        assert_eq!((extra, total), (godot_extra, godot_total), "{}", layout);
+        assert_eq!(rows, 6 + (extra as u32).div_ceil(8), "{}", layout);
+    }
+    assert_eq!(godot_rows, 4 + (godot_extra as u32).div_ceil(12));
+}
+
+#[test]
+fn padding_extrudes_every_cell()
+{
+    let sheet = RgbaImage::from_fn(24, 16, |x, y| Rgba([x as u8, y as u8, 0, 255]));
+    let (size, pad, margin) = (8, 2, 3);
+    let padded = padding::pad(&sheet, size, pad, margin);
+    assert_eq!(padded.dimensions(), (3*12 + 6, 2*12 + 6));
+    for (x, y, px) in padded.enumerate_pixels()
+    {
+        let inside = |v : u32, cells : u32| v >= margin && v < margin + cells*(size + pad*2);
+        if !inside(x, 3) || !inside(y, 2)
+        {
+            assert_eq!(px[3], 0, "margin pixel {},{} isn't empty", x, y);
+            continue;
+        }
+        // every pixel is a copy of the nearest pixel of its own cell
+        let (cx, cy) = ((x - margin)/(size + pad*2), (y - margin)/(size + pad*2));
+        let (ox, oy) = padding::cell_origin((cx, cy), size, pad, margin);
+        let sx = (x as i32 - ox as i32).clamp(0, size as i32 - 1) as u32;
+        let sy = (y as i32 - oy as i32).clamp(0, size as i32 - 1) as u32;
+        assert_eq!(*px, *sheet.get_pixel(cx*size + sx, cy*size + sy), "pixel {},{}", x, y);
+    }
+}
+
+#[test]
+fn scaling_keeps_cells_apart()
+{
+    let (red, blue) = (Rgba([255, 0, 0, 255]), Rgba([0, 0, 255, 255]));
+    // a red cell with a blue lower left triangle, next to a blue cell
+    let sheet = RgbaImage::from_fn(8, 4, |x, y| if x >= 4 || x < y { blue } else { red });
+    
+    let nearest = scale::scale(&sheet, 4, 3, "nearest").unwrap();
+    assert!(nearest.enumerate_pixels().all(|(x, y, px)| px == sheet.get_pixel(x/3, y/3)));
+    
+    for &factor in &[2, 3, 6]
+    {
+        let scaled = scale::scale(&sheet, 4, factor, "scale2x").unwrap();
+        assert_eq!(scaled.dimensions(), (8*factor, 4*factor));
+        // the blue cell stays blue, and the red cell keeps its red corner and blue corner
+        assert!((4*factor..8*factor).all(|x| (0..4*factor).all(|y| *scaled.get_pixel(x, y) == blue)));
+        assert_eq!(*scaled.get_pixel(4*factor - 1, 0), red);
+        assert_eq!(*scaled.get_pixel(0, 4*factor - 1), blue);
+    }
+    // the diagonal gets smoothed: the pixel just below it takes the colour of its neighbours
+    let scaled = scale::scale(&sheet, 4, 2, "scale2x").unwrap();
+    assert_eq!(*scaled.get_pixel(2, 3), blue);
+    assert_eq!(*nearest.get_pixel(3, 5), red);
+    
+    assert!(scale::scale(&sheet, 4, 5, "scale2x").is_err());
+}
+
+#[test]
+fn other_layouts_hold_the_same_cells()
+{
+    for arrangement in &[&BLOB, &BLOB_PACKED]
+    {
+        let masks = arrangement.masks.iter().flat_map(|row| row.iter()).filter(|&&mask| mask != 0).collect::<Vec<_>>();
+        assert_eq!(masks.len(), 47);
+        assert!(masks.iter().all(|&&mask| godot_cell(mask).is_some()));
+        assert!(masks.iter().enumerate().all(|(i, mask)| !masks[..i].contains(mask)), "{} has a bitmask twice", arrangement.name);
+        // sorted by the 8 bit blob bitmask, N=1 clockwise to NW=128
+        let blob = masks.iter().map(|&&mask| [TOP, TOP_RIGHT, RIGHT, BOTTOM_RIGHT, BOTTOM, BOTTOM_LEFT, LEFT, TOP_LEFT].iter().enumerate().filter(|(_, &bit)| mask & bit != 0).map(|(i, _)| 1 << i).sum::<u32>()).collect::<Vec<_>>();
+        assert!(blob.windows(2).all(|pair| pair[0] < pair[1]), "{} isn't sorted: {:?}", arrangement.name, blob);
+        assert_eq!((blob[1], blob[46]), (1, 255));
+    }
+    assert_eq!(BLOB.mask((0, 0)), 0);
+    assert_eq!(BLOB.mask((1, 0)), CENTER);
+    assert_eq!(BLOB.mask((7, 5)), 0x1ff);
+    assert_eq!(BLOB_PACKED.mask((0, 0)), CENTER);
+    assert_eq!((BLOB_PACKED.mask((6, 5)), BLOB_PACKED.mask((7, 5))), (0x1ff, 0));
+    
+    // every cell moves to the cell with its bitmask, alternatives go below
+    let size = 4;
+    let colour = |cell : (u32, u32)| Rgba([cell.0 as u8*16, cell.1 as u8*16, 0, 255]);
+    let sheet = RgbaImage::from_fn(12*size, 5*size, |x, y| if y < 4*size && godot_mask((x/size, y/size)) == 0 { Rgba([0, 0, 0, 0]) } else { colour((x/size, y/size)) });
+    let variant_cells = vec!(
+        variants::VariantCell { cell : (9, 2), mask : 0x1ff, probability : 0.5 },
+        variants::VariantCell { cell : (0, 4), mask : 0x1ff, probability : 0.5 },
+    );
+    let (moved, moved_variants) = variants::rearrange(&sheet, size, &variant_cells, &BLOB);
+    assert_eq!(moved.dimensions(), (8*size, 7*size));
+    for (x, y, px) in moved.enumerate_pixels()
+    {
+        let cell = (x/size, y/size);
+        let from = if cell == (0, 6) { Some((0, 4)) } else { godot_cell(BLOB.mask(cell)) };
+        let expected = from.map(colour).unwrap_or(Rgba([0, 0, 0, 0]));
+        assert_eq!(*px, expected, "cell {:?}", cell);
+    }
+    assert_eq!(moved_variants.iter().map(|variant| variant.cell).collect::<Vec<_>>(), vec!((7, 5), (0, 6)));
+    assert_eq!(variants::mask_of(&BLOB, &moved_variants, (0, 6)), 0x1ff);
+}
+
+// A tile of one colour with a frame of another one, as wide as the given edges (left, top, right, bottom).
+fn framed(size : u32, (left, top, right, bottom) : (u32, u32, u32, u32), frame : Rgba<u8>, inside : Rgba<u8>) -> RgbaImage
+{
+    RgbaImage::from_fn(size, size, |x, y| if x < left || y < top || x >= size-right || y >= size-bottom { frame } else { inside })
+}
+
+fn row_of(tiles : &[RgbaImage]) -> RgbaImage
+{
+    let size = tiles[0].width();
+    RgbaImage::from_fn(size*tiles.len() as u32, size, |x, y| *tiles[(x/size) as usize].get_pixel(x%size, y))
+}
+
+#[test]
+fn edges_are_detected_from_the_tiles()
+{
+    let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+    for &(mode, layout, _) in MODES.iter().filter(|(mode, _, _)| *mode != "basic" && *mode != "minitiles")
+    {
+        for _ in 0..16
+        {
+            let size = rng.range(2, 12);
+            let left = rng.range(1, size-1);
+            let top = rng.range(1, size-1);
+            let tileset = Tileset::new(&mut rng, size, left, top);
+            let edges = detect::detect_edges(&tileset.input(layout), mode, &default_tiles(mode), size);
+            assert_eq!(edges, (left, top, size-left, size-top), "mode {} size {}", mode, size);
+        }
+    }
+    
+    // basic and minitiles only have edges in tiles of their own, which can be different on every side
+    let (edge, fill) = (Rgba([200, 100, 0, 255]), Rgba([0, 160, 40, 255]));
+    let basic = row_of(&[framed(16, (0, 0, 0, 0), edge, fill), framed(16, (3, 2, 1, 4), edge, fill)]);
+    assert_eq!(detect::detect_edges(&basic, "basic", &default_tiles("basic"), 16), (3, 2, 1, 4));
+    let minitiles = row_of(&[
+        framed(16, (5, 4, 3, 6), edge, fill),
+        framed(16, (5, 0, 3, 0), edge, fill),
+        framed(16, (0, 4, 0, 6), edge, fill),
+        framed(16, (0, 0, 0, 0), edge, fill),
+        framed(16, (0, 0, 0, 0), edge, fill),
+    ]);
+    assert_eq!(detect::detect_edges(&minitiles, "minitiles", &default_tiles("minitiles"), 16), (5, 4, 3, 6));
+}
+
+#[test]
+fn undetectable_edges_fall_back_to_half_the_tile()
+{
+    // translucent noise has no uniform interior to compare against
+    let mut rng = Rng(0x1234_5678_9abc_def1);
+    let noise = RgbaImage::from_fn(2*15, 15, |_, _| Rgba([rng.next() as u8, rng.next() as u8, rng.next() as u8, 128]));
+    assert_eq!(detect::detect_edges(&noise, "basic", &default_tiles("basic"), 15), (7, 7, 8, 8));
+    
+    // an edge that would be 0 wide is taken from the other side
+    let (edge, fill) = (Rgba([200, 100, 0, 255]), Rgba([0, 160, 40, 255]));
+    let one_sided = row_of(&[framed(16, (0, 0, 0, 0), edge, fill), framed(16, (3, 2, 0, 4), edge, fill)]);
+    assert_eq!(detect::detect_edges(&one_sided, "basic", &default_tiles("basic"), 16), (3, 2, 13, 4));
+    
+    // a one pixel outline in front of a flat fill matches it from the second line on, but its rounded corners go further in
+    let rounded = RgbaImage::from_fn(16, 16, |x, y| if x.min(15-x) + y.min(15-y) < 5 || x % 15 == 0 || y % 15 == 0 { edge } else { fill });
+    let outlined = row_of(&[framed(16, (0, 0, 0, 0), edge, fill), rounded]);
+    assert_eq!(detect::detect_edges(&outlined, "basic", &default_tiles("basic"), 16), (8, 8, 8, 8));
+}
+
+#[test]
+fn auto_edges_check_the_tile_list_first()
+{
+    let args = ["basic.png", "unused.png", "edges=auto", "5,5", "6,6"].iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+    let job = Job::from_args(&args).unwrap();
+    assert_eq!(job.settings(&common::example_image("basic.png")).err(), Some("the tile list points outside of the input image".to_string()));
+}
+
+// An input of columns x rows opaque tiles, with the given cells left transparent.
+fn tile_grid(columns : u32, rows : u32, size : u32, empty : &[(u32, u32)]) -> RgbaImage
+{
+    RgbaImage::from_fn(columns*size, rows*size, |x, y| if empty.contains(&(x/size, y/size)) { Rgba([0, 0, 0, 0]) } else { Rgba([90, 60, 30, 255]) })
+}
+
+// mode, width in tiles, height in tiles, cells that are empty
+type InputShape = (&'static str, u32, u32, &'static [(u32, u32)]);
+
+#[test]
+fn layouts_are_detected_from_the_input_shape()
+{
+    let shapes : &[InputShape] = &[
+        ("basic", 2, 1, &[]),
+        ("basic", 1, 2, &[]),
+        ("3x3", 3, 3, &[]),
+        ("3x3plus", 4, 3, &[(3, 1), (3, 2)]),
+        ("4x4", 4, 4, &[]),
+        ("4x4plus", 5, 4, &[(4, 1), (4, 2), (4, 3)]),
+        ("minitiles", 5, 1, &[]),
+    ];
+    for &(mode, columns, rows, empty) in shapes
+    {
+        let img = tile_grid(columns, rows, 16, empty);
+        assert_eq!(detect::detect_layout(&img, None, None), Ok((mode.to_string(), 16)), "{} {}x{}", mode, columns, rows);
+        if (columns, rows) != (1, 2)
+        {
+            assert_eq!(detect::input_shape(mode), Some((columns, rows)));
+        }
+    }
+    // the plus modes need their unused cells empty, otherwise it's a different shape or nothing
+    assert!(detect::detect_layout(&tile_grid(4, 3, 16, &[]), None, None).is_err());
+    // basic_border takes the input of basic
+    assert_eq!(detect::detect_layout(&tile_grid(2, 1, 16, &[]), Some("basic_border"), None), Ok(("basic_border".to_string(), 16)));
+    
+    // 48x48 is 3x3 tiles of 16 or 4x4 tiles of 12, and only 16 is a power of two
+    assert_eq!(detect::detect_layout(&tile_grid(3, 3, 16, &[]), None, None), Ok(("3x3".to_string(), 16)));
+    assert_eq!(detect::detect_layout(&tile_grid(3, 3, 16, &[]), Some("4x4"), None), Ok(("4x4".to_string(), 12)));
+    
+    // 36x36 is 3x3 tiles of 12 or 4x4 tiles of 9, neither a power of two
+    let ambiguous = tile_grid(3, 3, 12, &[]);
+    assert_eq!(detect::detect_layout(&ambiguous, None, None), Err("the layout of a 36x36 image is ambiguous (mode=3x3 size=12, mode=4x4 size=9), please specify mode= or size=".to_string()));
+    assert_eq!(detect::detect_layout(&ambiguous, None, Some(9)), Ok(("4x4".to_string(), 9)));
+    
+    assert_eq!(detect::detect_layout(&tile_grid(2, 3, 32, &[]), None, None), Err("64x96 looks like an RPG Maker autotile block, which autotyler has no mode for".to_string()));
+    assert_eq!(detect::detect_layout(&tile_grid(7, 2, 16, &[]), None, None), Err("could not guess the layout of a 112x32 image, please specify mode= and size=".to_string()));
+}
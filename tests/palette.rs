@@ -0,0 +1,63 @@
+use image::RgbaImage;
+
+use autotyler::job;
+use autotyler::palette;
+use autotyler::palette::Palette;
+
+mod common;
+
+#[test]
+fn indexed_palette()
+{
+    let input = common::example_image("minitiles2.png");
+    // every colour of the input, in an order that sorting wouldn't give, after a transparent entry for the blank cell and an unused one
+    let mut colours = vec!([0, 0, 0, 0], [1, 2, 3, 255]);
+    for px in input.pixels()
+    {
+        let colour = if px[3] == 0 { [0, 0, 0, 0] } else { px.0 };
+        if !colours.contains(&colour)
+        {
+            colours.push(colour);
+        }
+    }
+    colours[2..].reverse();
+    let palette = Palette { depth : if colours.len() <= 16 { 4 } else { 8 }, colours };
+    
+    let in_filename = format!("{}/indexed.png", env!("CARGO_TARGET_TMPDIR"));
+    let out_filename = format!("{}/indexed_output.png", env!("CARGO_TARGET_TMPDIR"));
+    assert!(palette::save(&input, &palette, &in_filename).unwrap());
+    let mut args = vec!(in_filename.clone(), out_filename.clone(), "mode=minitiles".to_string());
+    args.extend((0..5).map(|x| format!("{},0", x)));
+    
+    // outputs are truecolour unless palette=keep is given
+    job::run_config(&job::config_from_args(&args).unwrap()).unwrap();
+    assert!(palette::read(&out_filename).unwrap().is_none());
+    args.push("palette=keep".to_string());
+    let config = job::config_from_args(&args).unwrap();
+    job::run_config(&config).unwrap();
+    
+    assert!(palette::read(&out_filename).unwrap() == Some(palette));
+    let expected = common::example_image("minitiles2_output.png");
+    let out_img = image::open(&out_filename).unwrap().to_rgba();
+    assert!(out_img.pixels().zip(expected.pixels()).all(|(a, b)| a == b || (a[3] == 0 && b[3] == 0)));
+    
+    // an opaque palette gets a transparent entry at the end, unless it's full
+    let opaque = Palette { depth : 2, colours : vec!([255, 0, 0, 255], [0, 255, 0, 255], [0, 0, 255, 255], [255, 255, 255, 255]) };
+    let sheet = RgbaImage::from_fn(8, 4, |x, y| if x < 4 { image::Rgba(opaque.colours[(y as usize + x as usize) % 4]) } else { image::Rgba([0, 0, 0, 0]) });
+    let opaque_filename = format!("{}/opaque.png", env!("CARGO_TARGET_TMPDIR"));
+    assert!(palette::save(&sheet, &opaque, &opaque_filename).unwrap());
+    let mut extended = opaque.clone();
+    extended.colours.push([0, 0, 0, 0]);
+    extended.depth = 4;
+    assert!(palette::read(&opaque_filename).unwrap() == Some(extended));
+    assert!(image::open(&opaque_filename).unwrap().to_rgba() == sheet);
+    let full = Palette { depth : 8, colours : (0..256).map(|i| [i as u8, 0, 0, 255]).collect() };
+    let sheet = RgbaImage::from_fn(8, 4, |x, _| if x < 4 { image::Rgba([0, 0, 0, 255]) } else { image::Rgba([0, 0, 0, 0]) });
+    assert!(!palette::save(&sheet, &full, &opaque_filename).unwrap());
+    
+    // and palette=rgba, given after it, writes truecolour again
+    args.push("palette=rgba".to_string());
+    let config = job::config_from_args(&args).unwrap();
+    job::run_config(&config).unwrap();
+    assert!(palette::read(&out_filename).unwrap().is_none());
+}
@@ -0,0 +1,44 @@
+use autotyler::assemble;
+use autotyler::assemble::Settings;
+use autotyler::masks;
+use autotyler::reverse;
+use autotyler::variants;
+
+mod common;
+
+// Reversing a committed output has to give input tiles that generate exactly that output again.
+fn check_reverse(mode : &str, origin_tile_list : Vec<(u32, u32)>, (columns, rows) : (u32, u32), sheet : &str)
+{
+    let sheet_img = common::example_image(sheet);
+    let settings = Settings { mode : mode.to_string(), size : 16, edges : (8, 8, 8, 8), border : 2, origin_tile_list };
+    let reversed = reverse::reverse(&sheet_img, &settings, (columns*16, rows*16), &masks::GODOT).unwrap();
+    assert_eq!(reversed.unexplained_total, 0, "{}: {:?}", sheet, reversed.unexplained);
+    assert!(assemble::assemble(&reversed.input, &settings) == sheet_img, "{}: reversed input doesn't give the sheet back", sheet);
+}
+
+#[test]
+fn reverse()
+{
+    check_reverse("3x3", vec!((0, 0)), (3, 3), "3x3_output.png");
+    check_reverse("3x3plus", vec!((0, 0), (3, 0)), (4, 3), "3x3plus_output.png");
+    check_reverse("4x4plus", vec!((0, 0), (4, 0)), (5, 4), "4x4plus_output.png");
+    check_reverse("minitiles", vec!((0, 0), (1, 0), (2, 0), (3, 0), (4, 0)), (5, 1), "minitiles2_output.png");
+    
+    // 3x3plus has no tiles for the one wide parts of the 4x4 modes
+    let sheet_img = common::example_image("4x4plus_output.png");
+    let settings = Settings { mode : "3x3plus".to_string(), size : 16, edges : (8, 8, 8, 8), border : 2, origin_tile_list : vec!((0, 0), (3, 0)) };
+    assert!(reverse::reverse(&sheet_img, &settings, (64, 48), &masks::GODOT).unwrap().unexplained_total > 0);
+    
+    // sheets in the other layouts give the same tiles back
+    let sheet_img = common::example_image("3x3_output.png");
+    let settings = Settings { mode : "3x3".to_string(), size : 16, edges : (8, 8, 8, 8), border : 2, origin_tile_list : vec!((0, 0)) };
+    let godot = reverse::reverse(&sheet_img, &settings, (48, 48), &masks::GODOT).unwrap();
+    for layout in &[&masks::BLOB, &masks::BLOB_PACKED]
+    {
+        let (rearranged, _) = variants::rearrange(&sheet_img, 16, &[], layout);
+        let reversed = reverse::reverse(&rearranged, &settings, (48, 48), layout).unwrap();
+        assert_eq!(reversed.unexplained_total, 0, "{}: {:?}", layout.name, reversed.unexplained);
+        assert!(reversed.input == godot.input, "{}", layout.name);
+        assert!(reverse::reverse(&sheet_img, &settings, (48, 48), layout).is_err());
+    }
+}
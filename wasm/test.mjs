@@ -0,0 +1,54 @@
+// Runs the wasm build of autotyler through its C ABI, as a web page or editor plugin would.
+// cargo build --release --lib --target wasm32-unknown-unknown && node wasm/test.mjs
+
+import { readFileSync } from "fs";
+
+const path = new URL("../target/wasm32-unknown-unknown/release/autotyler.wasm", import.meta.url);
+const { instance } = await WebAssembly.instantiate(readFileSync(path), {});
+const wasm = instance.exports;
+const MODES = ["basic", "basic_border", "3x3", "3x3plus", "4x4", "4x4plus", "minitiles"];
+
+// Runs one mode over an RGBA buffer. Options are the fields of AssembleOptions, tiles a list of [x, y].
+function assemble(rgba, width, height, { mode = "basic", size = 16, edges = [0, 0, 0, 0], border = 2, offset = [0, 0], tiles = [] } = {})
+{
+    const input = wasm.autotyler_alloc(rgba.length);
+    new Uint8Array(wasm.memory.buffer, input, rgba.length).set(rgba);
+    const tileList = tiles.length ? wasm.autotyler_alloc(tiles.length*8) : 0;
+    if (tileList)
+        new Uint32Array(wasm.memory.buffer, tileList, tiles.length*2).set(tiles.flat());
+    const options = wasm.autotyler_alloc(11*4);
+    new Uint32Array(wasm.memory.buffer, options, 11).set([MODES.indexOf(mode), size, ...edges, border, ...offset, tiles.length, tileList]);
+    
+    const result = wasm.autotyler_assemble(options, input, width, height);
+    const [pixels, outWidth, outHeight, error, errorLength] = new Uint32Array(wasm.memory.buffer, result, 5);
+    const ret = error
+        ? { error: new TextDecoder().decode(new Uint8Array(wasm.memory.buffer, error, errorLength)) }
+        : { width: outWidth, height: outHeight, rgba: new Uint8Array(wasm.memory.buffer, pixels, outWidth*outHeight*4).slice() };
+    
+    wasm.autotyler_free_result(result);
+    wasm.autotyler_free(options, 11*4);
+    wasm.autotyler_free(input, rgba.length);
+    if (tileList)
+        wasm.autotyler_free(tileList, tiles.length*8);
+    return ret;
+}
+
+// two 16x16 tiles side by side: an opaque fill tile and an edge tile that's transparent outside of a frame
+const input = new Uint8Array(32*16*4);
+for (let y = 0; y < 16; y++)
+    for (let x = 0; x < 32; x++)
+        input.set(x < 16 || x == 16 || x == 31 || y == 0 || y == 15 ? [x*8, y*16, 128, 255] : [0, 0, 0, 0], (y*32 + x)*4);
+
+const out = assemble(input, 32, 16);
+if (out.error || out.width != 192 || out.height != 64)
+    throw new Error(`unexpected output: ${out.error ?? `${out.width}x${out.height}`}`);
+// the blank cell at 10,1 is empty
+for (let y = 16; y < 32; y++)
+    for (let x = 160; x < 176; x++)
+        if (out.rgba[(y*192 + x)*4 + 3] != 0)
+            throw new Error(`blank cell isn't empty at ${x},${y}`);
+
+const bad = assemble(input, 32, 16, { mode: "3x3" });
+if (!bad.error)
+    throw new Error("a 3x3 tile list outside of the input should fail");
+console.log(`ok: ${out.width}x${out.height}, and errors come through: ${bad.error}`);